@@ -1,73 +1,1206 @@
+[CmdletBinding()]
+param(
+    # Compare two collected bundles (WF-Diag.zip files or extracted folders) instead of collecting: -Compare old.zip, new.zip
+    [string[]]$Compare,
+    # Print -Compare results as JSON instead of a report
+    [switch]$Json,
+    # Analyze a collected bundle (WF-Diag.zip, an extracted folder or a bare Minidump folder) instead of collecting
+    [string]$Analyze,
+    # Write a redacted copy (<name>-redacted.zip) of an already collected archive instead of collecting
+    [string]$RedactArchive,
+    # Copy the files of the tasks named by -Task (wildcards allowed) out of a bundle, using its manifest.json
+    [string]$Extract,
+    [Alias("Task")]
+    [string[]]$ExtractTask,
+    # Config file with defaults for the other parameters (wfdiag.toml or .json)
+    [string]$Config,
+    # No progress window or dialogs; print a JSON summary of the run on stdout (use -Verbose to echo the tool log)
+    [switch]$Quiet,
+    # Lowest level written to tool-logs\wfdiag.log; the log moves to wfdiag.1.log once it grows past -LogMaxMB
+    [ValidateSet("INFO", "WARN", "ERROR")]
+    [string]$LogLevel = "INFO",
+    [int]$LogMaxMB = 5,
+    # Restart elevated through the UAC prompt when not running as administrator
+    [switch]$Elevate,
+    # Run in the terminal: progress bars instead of the window, plain "Progress: n%" lines when output is redirected
+    [switch]$Console,
+    # Pick tasks from a list in the terminal, show each task as it finishes and browse the output afterwards (implies -Console)
+    [switch]$Interactive,
+    # Choose the tasks in a window, grouped by category, before collecting
+    [switch]$SelectTasks,
+    # Language of the window and dialogs (de, es, pt-BR, ...); defaults to the Windows display language
+    [string]$Language,
+    # "json" writes every progress update as a JSON line on stderr, or to the named pipe given by -ProgressPipe
+    [ValidateSet("text", "json")]
+    [string]$ProgressFormat = "text",
+    [string]$ProgressPipe,
+    # List what each selected task would run and touch, then exit without collecting anything
+    [switch]$DryRun,
+    # Describe tasks (wildcards allowed) instead of collecting: -TaskInfo "Minidump*"
+    [string]$TaskInfo,
+    # Check that the tools, WMI and output folder the tasks need are usable, without collecting
+    [switch]$Doctor,
+    # Continue an interrupted collection (crash, reboot): tasks that completed last time are not run again
+    [switch]$Resume,
+    # Manage a daily background collection: -Schedule install -At 03:00 -Preset bsod -KeepArchives 7, or remove/status
+    [ValidateSet("install", "remove", "status")]
+    [string]$Schedule,
+    [string]$At = "03:00",
+    # Delete all but the newest N archives in the output folder after a run
+    [int]$KeepArchives,
+    # Also delete archives older than N days, and the oldest ones once all archives together exceed N MB
+    [int]$KeepDays,
+    [int]$KeepTotalMB,
+    # Keep this run's snapshot as the baseline that later runs are compared against
+    [switch]$SaveBaseline,
+    # Only run tasks of these categories: -Category Network,Storage (see $taskCategories for the list)
+    [string[]]$Category,
+    # Run a curated set of tasks for a common problem (see $taskPresets)
+    [ValidateSet("bsod", "network", "performance", "quick", "slow-boot")]
+    [string]$Preset,
+    # Skip these tasks by name (wildcards allowed): -Exclude DXDiag,"Event Logs"
+    [string[]]$Exclude,
+    # Run at most this many tasks at the same time (default: the number of processors, at least 4)
+    [int]$MaxJobs,
+    # Sample CPU, memory, disk queue and top processes for this many minutes (timeline CSV/JSON); every -MonitorInterval seconds
+    [int]$MonitorMinutes,
+    [int]$MonitorInterval = 15,
+    # Also write the structured results as a SQL script (load with: sqlite3 wfdiag.db < WindowsForum-Results.sql); implies -Csv
+    [switch]$Sql,
+    # Also save list-shaped reports (processes, services, drivers, programs) as CSV for Excel
+    [switch]$Csv,
+    # Also export the findings as SARIF 2.1.0 (WindowsForum-Findings.sarif) for ticketing systems and triage bots
+    [switch]$Sarif,
+    # Stream each finished task as a JSON line to this file, or to stdout with "-"
+    [string]$Ndjson,
+    # Encrypt the archive with AES-256 (WF-Diag.zip.aes); a password is generated unless -Password is given
+    [switch]$Encrypt,
+    [string]$Password,
+    # Set by the elevated and "Retry failed" relaunches: -Password protected for the current user, so it is not shown in plain text
+    [string]$ProtectedPassword,
+    # Decrypt an encrypted archive instead of collecting: -Decrypt WF-Diag.zip.aes -Password ...
+    [string]$Decrypt,
+    # Archive format: zip (default), 7z (needs 7-Zip) or tar.zst (needs zstd.exe); falls back to zip
+    [ValidateSet("zip", "7z", "tar.zst")]
+    [string]$ArchiveFormat = "zip",
+    # Optimal (default, smallest), Fastest, or Store (no compression) for quick collections
+    [ValidateSet("Optimal", "Fastest", "Store")]
+    [string]$Compression = "Optimal",
+    # Split the final archive into parts of this many MB (for forum attachment limits), e.g. -SplitSizeMB 50
+    [int]$SplitSizeMB,
+    # Scrub user and computer names, public IPs, MAC addresses, serial numbers and Wi-Fi names from text reports
+    [switch]$Redact,
+    # Folder for the results and the archive (default: Desktop)
+    [string]$OutputPath,
+    # Archive file name without extension; {host}, {date} and {time} are filled in, e.g. "WF-Diag_{host}_{date}"
+    [string]$ArchiveName,
+    # Write a detached signature (<archive>.sig) with the signing key of this machine or -SigningKey
+    [switch]$Sign,
+    [string]$SigningKey,
+    # Check an archive against its .sig instead of collecting: -Verify WF-Diag.zip -TrustedKey 3f:a1:...
+    [string]$Verify,
+    # Key fingerprints -Verify accepts (default: the signing key of this machine or -SigningKey)
+    [string[]]$TrustedKey,
+    # POST the finished archive to this HTTPS endpoint and show the returned share link
+    [string]$UploadUrl
+)
 $version = "2.0.6"
 Add-Type -AssemblyName System.Windows.Forms
 Add-Type -AssemblyName System.Drawing
 Add-Type -AssemblyName System.IO.Compression.FileSystem
+Add-Type -AssemblyName System.IO.Compression
 $script:stopScript = $false
+$script:stopRequested = $false
+$script:paused = $false
+$script:collectionFinished = $false
+
+# Minimal TOML reader for wfdiag.toml: key = value lines with strings, numbers, booleans and string
+# arrays. [sections] only group keys for readability; every key is a parameter name.
+function ConvertFrom-WFToml {
+    param([string[]]$Lines)
+    $result = [ordered]@{}
+    $lineNumber = 0
+    foreach ($line in $Lines) {
+        $lineNumber++
+        $line = $line.Trim()
+        if (!$line -or $line.StartsWith("#") -or $line.StartsWith("[")) { continue }
+        if ($line -notmatch '^([\w-]+)\s*=\s*(.+)$') { throw "Unsupported TOML line: $line" }
+        $key = $Matches[1]
+        # Strip a trailing comment that is not inside a string
+        $raw = ($Matches[2] -replace '\s+#[^"'']*$', '').Trim()
+        $parseValue = {
+            param($text)
+            if ($text -match '^"(.*)"$') {
+                try { [regex]::Unescape($Matches[1]) }
+                catch { throw "Invalid escape in the value of $key on line ${lineNumber}: $line. Double the backslashes (""D:\\Support"") or use single quotes ('D:\Support')" }
+            }
+            elseif ($text -match "^'(.*)'$") { $Matches[1] }
+            elseif ($text -in "true", "false") { $text -eq "true" }
+            elseif ($text -match '^-?\d+$') { [int]$text }
+            else { throw "Unsupported TOML value for ${key}: $text" }
+        }
+        $result[$key] = if ($raw -match '^\[(.*)\]$') {
+            @([regex]::Matches($Matches[1], '"(?:[^"\\]|\\.)*"|''[^'']*''|[^,\s]+') | ForEach-Object { & $parseValue $_.Value })
+        } else { & $parseValue $raw }
+    }
+    [PSCustomObject]$result
+}
+
+# Defaults for parameters come from -Config, else wfdiag.toml next to the tool, else config.toml or
+# config.json in %LOCALAPPDATA%\WindowsForum; command-line parameters win over the config file. Read before
+# anything else looks at the parameters, so config values are split, validated and acted on like typed ones.
+$toolFolder = if ($PSScriptRoot) { $PSScriptRoot } else { Split-Path -Path ([System.Diagnostics.Process]::GetCurrentProcess().MainModule.FileName) }
+$configPath = if ($Config) { $Config } else {
+    @((Join-Path -Path $toolFolder -ChildPath "wfdiag.toml"), (Join-Path -Path $env:LOCALAPPDATA -ChildPath "WindowsForum\config.toml"),
+        (Join-Path -Path $env:LOCALAPPDATA -ChildPath "WindowsForum\config.json")) | Where-Object { Test-Path -Path $_ } | Select-Object -First 1
+}
+$config = $null
+if ($configPath) {
+    $config = if ($configPath -match '\.toml$') { ConvertFrom-WFToml (Get-Content -Path $configPath) } else { Get-Content -Path $configPath -Raw | ConvertFrom-Json }
+    $configurable = "OutputPath", "ArchiveName", "ArchiveFormat", "Compression", "SplitSizeMB", "Encrypt", "Sign", "SigningKey", "TrustedKey", "Redact",
+        "Elevate", "At", "UploadUrl", "Preset", "Category", "Exclude", "KeepArchives", "KeepDays", "KeepTotalMB", "MonitorMinutes", "MonitorInterval", "MaxJobs", "Csv", "Sql", "Sarif", "Ndjson", "SaveBaseline", "LogLevel", "LogMaxMB"
+    foreach ($property in $config.PSObject.Properties) {
+        if ($configurable -notcontains $property.Name) { Write-Warning "Ignoring unknown setting $($property.Name) in $configPath"; continue }
+        if (!$PSBoundParameters.ContainsKey($property.Name)) { Set-Variable -Name $property.Name -Value $property.Value }
+    }
+}
+# A relaunch (see Get-WFRelaunchParameters) hands the password over protected for this user
+if ($ProtectedPassword) { $Password = (New-Object System.Net.NetworkCredential("", (ConvertTo-SecureString $ProtectedPassword))).Password }
+
+# Reads the snapshot (WindowsForum-Snapshot.json) from a WF-Diag.zip or an extracted WindowsForum folder
+function Read-WFSnapshot {
+    param([string]$Path)
+    if ((Get-Item -Path $Path).PSIsContainer) {
+        return Get-Content -Path (Join-Path -Path $Path -ChildPath "WindowsForum-Snapshot.json") -Raw | ConvertFrom-Json
+    }
+    $zip = [System.IO.Compression.ZipFile]::OpenRead((Resolve-Path -Path $Path).Path)
+    try {
+        $entry = $zip.Entries | Where-Object { $_.Name -eq "WindowsForum-Snapshot.json" } | Select-Object -First 1
+        if (!$entry) { throw "$Path does not contain WindowsForum-Snapshot.json (collected with an older version?)" }
+        $reader = New-Object System.IO.StreamReader($entry.Open())
+        try { $reader.ReadToEnd() | ConvertFrom-Json } finally { $reader.Dispose() }
+    } finally { $zip.Dispose() }
+}
+
+# Diffs two snapshots and returns one change record per difference
+function Compare-WFSnapshot {
+    param($Old, $New)
+    $change = { param($category, $changeType, $item, $oldValue, $newValue) [PSCustomObject]@{ Category = $category; Change = $changeType; Item = $item; Old = $oldValue; New = $newValue } }
+    # Keyed collections: added, removed and changed entries
+    $keyedSets = @(
+        @{ Category = "Driver"; Property = "Drivers"; Key = { "$($_.DeviceName) [$($_.Provider)]" }; Value = { $_.Version } }
+        @{ Category = "Service"; Property = "Services"; Key = { $_.Name }; Value = { $_.StartType } }
+        @{ Category = "Startup"; Property = "StartupItems"; Key = { $_.Name }; Value = { $_.Command } }
+    )
+    foreach ($set in $keyedSets) {
+        $oldItems = @{}; $newItems = @{}
+        $Old.($set.Property) | ForEach-Object { $oldItems[(& $set.Key)] = (& $set.Value) }
+        $New.($set.Property) | ForEach-Object { $newItems[(& $set.Key)] = (& $set.Value) }
+        foreach ($key in $newItems.Keys | Sort-Object) {
+            if (!$oldItems.ContainsKey($key)) { & $change $set.Category "Added" $key $null $newItems[$key] }
+            elseif ("$($oldItems[$key])" -ne "$($newItems[$key])") { & $change $set.Category "Changed" $key $oldItems[$key] $newItems[$key] }
+        }
+        foreach ($key in $oldItems.Keys | Sort-Object) {
+            if (!$newItems.ContainsKey($key)) { & $change $set.Category "Removed" $key $oldItems[$key] $null }
+        }
+    }
+    foreach ($hotfix in $New.Hotfixes | Where-Object { $_ -notin $Old.Hotfixes }) { & $change "Update" "Installed" $hotfix $null $null }
+    # Error events are compared by provider and event ID; only new or growing ones are interesting
+    $oldEvents = @{}
+    $Old.ErrorEvents | ForEach-Object { $oldEvents["$($_.Provider) $($_.EventId)"] = $_.Count }
+    foreach ($errorEvent in $New.ErrorEvents) {
+        $key = "$($errorEvent.Provider) $($errorEvent.EventId)"
+        if (!$oldEvents.ContainsKey($key)) { & $change "Event" "New error" $key $null $errorEvent.Count }
+        elseif ($errorEvent.Count -gt $oldEvents[$key]) { & $change "Event" "More frequent" $key $oldEvents[$key] $errorEvent.Count }
+    }
+    foreach ($disk in $New.Disks) {
+        $oldDisk = $Old.Disks | Where-Object { $_.Drive -eq $disk.Drive }
+        if ($oldDisk -and [math]::Abs($disk.FreeGB - $oldDisk.FreeGB) -ge 1) {
+            & $change "Disk" "Free space $(if ($disk.FreeGB -gt $oldDisk.FreeGB) { '+' })$([math]::Round($disk.FreeGB - $oldDisk.FreeGB, 1)) GB" $disk.Drive $oldDisk.FreeGB $disk.FreeGB
+        }
+    }
+}
+
+# Finds an external archiver on PATH or in its usual install folder
+function Find-WFArchiver {
+    param([string]$Name, [string[]]$KnownPaths)
+    $command = Get-Command $Name -CommandType Application -ErrorAction SilentlyContinue | Select-Object -First 1
+    if ($command) { return $command.Source }
+    $KnownPaths | Where-Object { Test-Path -Path $_ } | Select-Object -First 1
+}
+
+# Already-compressed files gain nothing from Deflate, so they are always stored
+$storedExtensions = @(".zip", ".cab", ".7z", ".gz", ".zst", ".png", ".jpg", ".jpeg", ".aes")
+
+# Writes a zip with a per-file compression level instead of one level for everything
+function New-WFZip {
+    param([string]$Source, [string]$Destination, [string]$Compression)
+    $level = switch ($Compression) {
+        "Fastest" { [System.IO.Compression.CompressionLevel]::Fastest }
+        "Store" { [System.IO.Compression.CompressionLevel]::NoCompression }
+        default { [System.IO.Compression.CompressionLevel]::Optimal }
+    }
+    $root = (Resolve-Path -Path $Source).Path.TrimEnd('\') + '\'
+    $zip = [System.IO.Compression.ZipFile]::Open($Destination, [System.IO.Compression.ZipArchiveMode]::Create)
+    try {
+        foreach ($file in Get-ChildItem -Path $root -Recurse -File -Force) {
+            $entryLevel = if ($storedExtensions -contains $file.Extension.ToLower()) { [System.IO.Compression.CompressionLevel]::NoCompression } else { $level }
+            [void][System.IO.Compression.ZipFileExtensions]::CreateEntryFromFile($zip, $file.FullName, $file.FullName.Substring($root.Length).Replace('\', '/'), $entryLevel)
+        }
+    } finally { $zip.Dispose() }
+}
+
+# Compresses the results folder and returns the path of the archive that was written.
+# 7z and tar.zst shrink event logs and dumps far more than zip but need external tools.
+function New-WFArchive {
+    param([string]$Source, [string]$BasePath, [string]$Format, [string]$Compression = "Optimal")
+    if ($Format -eq "7z") {
+        $sevenZip = Find-WFArchiver "7z.exe" @("$env:ProgramFiles\7-Zip\7z.exe", "${env:ProgramFiles(x86)}\7-Zip\7z.exe")
+        if ($sevenZip) {
+            $destination = "$BasePath.7z"
+            $mx = switch ($Compression) { "Fastest" { 1 } "Store" { 0 } default { 9 } }
+            & $sevenZip a -t7z "-mx=$mx" -bd -y $destination "$Source\*" | Out-Null
+            if ($LASTEXITCODE -eq 0) { return $destination }
+            Write-ToolLog "7-Zip exited with $LASTEXITCODE, falling back to zip" "WARN"
+            Remove-Item -Path $destination -Force -ErrorAction SilentlyContinue
+        } else {
+            Write-ToolLog "7z.exe not found, falling back to zip" "WARN"
+        }
+    } elseif ($Format -eq "tar.zst") {
+        $zstd = Find-WFArchiver "zstd.exe" @("$env:ProgramFiles\zstd\zstd.exe")
+        if ($zstd) {
+            $tarPath = "$BasePath.tar"
+            $destination = "$BasePath.tar.zst"
+            & "$env:SystemRoot\System32\tar.exe" -cf $tarPath -C $Source . 2>&1 | Out-Null
+            $zstdLevel = switch ($Compression) { "Fastest" { "-1" } "Store" { "--fast=100" } default { "-19" } }
+            if ($LASTEXITCODE -eq 0) { & $zstd -q $zstdLevel -T0 -f --rm $tarPath -o $destination 2>&1 | Out-Null }
+            if ($LASTEXITCODE -eq 0) { return $destination }
+            Write-ToolLog "tar/zstd exited with $LASTEXITCODE, falling back to zip" "WARN"
+            Remove-Item -Path $tarPath, $destination -Force -ErrorAction SilentlyContinue
+        } else {
+            Write-ToolLog "zstd.exe not found, falling back to zip" "WARN"
+        }
+    }
+    $destination = "$BasePath.zip"
+    New-WFZip $Source $destination $Compression
+    $destination
+}
+
+# Cuts an archive into numbered parts (WF-Diag.zip.001, .002, ...) and writes a manifest with
+# sizes, hashes and how to join them again; returns the part paths
+function Split-WFArchive {
+    param([string]$Path, [int]$PartSizeMB)
+    $partSize = [int64]$PartSizeMB * 1MB
+    $buffer = New-Object byte[] 1MB
+    $parts = @()
+    $inputStream = [System.IO.File]::OpenRead($Path)
+    try {
+        $totalSize = $inputStream.Length
+        while ($inputStream.Position -lt $inputStream.Length) {
+            $partPath = "{0}.{1:D3}" -f $Path, ($parts.Count + 1)
+            $output = [System.IO.File]::Create($partPath)
+            try {
+                $remaining = [math]::Min($partSize, $inputStream.Length - $inputStream.Position)
+                while ($remaining -gt 0) {
+                    $read = $inputStream.Read($buffer, 0, [math]::Min($buffer.Length, $remaining))
+                    $output.Write($buffer, 0, $read)
+                    $remaining -= $read
+                }
+            } finally { $output.Dispose() }
+            $parts += $partPath
+        }
+    } finally { $inputStream.Dispose() }
+    $fileName = Split-Path -Path $Path -Leaf
+    $partNames = $parts | ForEach-Object { Split-Path -Path $_ -Leaf }
+    $manifest = @(
+        "$fileName was split into $($parts.Count) parts of up to $PartSizeMB MB ($totalSize bytes in total)."
+        "Download all parts into one folder and join them in a Command Prompt or PowerShell window with:"
+        "    cmd /c copy /b $($partNames -join '+') $fileName"
+        "or in Windows PowerShell 5.1:"
+        "    Get-Content $($partNames -join ', ') -Encoding Byte -ReadCount 0 | Set-Content $fileName -Encoding Byte"
+        "or in PowerShell 7:"
+        "    Get-Content $($partNames -join ', ') -AsByteStream -ReadCount 0 | Set-Content $fileName -AsByteStream"
+        ""
+        "{0,-24} {1,12}  {2}" -f "Part", "Bytes", "SHA256"
+    )
+    foreach ($part in $parts) {
+        $manifest += "{0,-24} {1,12}  {2}" -f (Split-Path -Path $part -Leaf), (Get-Item -Path $part).Length, (Get-FileHash -Path $part -Algorithm SHA256).Hash
+    }
+    $manifest += "{0,-24} {1,12}  {2}" -f $fileName, $totalSize, (Get-FileHash -Path $Path -Algorithm SHA256).Hash
+    $manifest | Out-File "$Path.manifest.txt"
+    Remove-Item -Path $Path -Force
+    $parts
+}
+
+# Encrypted archives: "WFENC1" + salt + IV + AES-256-CBC ciphertext + HMAC-SHA256 over everything before it.
+# Keys come from the password via PBKDF2-SHA256. Plain .zip files have no AES support in .NET, so the whole
+# archive is wrapped and -Decrypt restores the original WF-Diag.zip.
+$archiveMagic = [System.Text.Encoding]::ASCII.GetBytes("WFENC1")
+function Get-WFArchiveKeys {
+    param([string]$Password, [byte[]]$Salt)
+    $kdf = New-Object System.Security.Cryptography.Rfc2898DeriveBytes($Password, $Salt, 200000, [System.Security.Cryptography.HashAlgorithmName]::SHA256)
+    try { @{ Encryption = $kdf.GetBytes(32); Mac = $kdf.GetBytes(32) } } finally { $kdf.Dispose() }
+}
+function New-WFArchivePassword {
+    $alphabet = "ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz23456789"
+    $bytes = New-Object byte[] 20
+    [System.Security.Cryptography.RandomNumberGenerator]::Create().GetBytes($bytes)
+    -join ($bytes | ForEach-Object { $alphabet[$_ % $alphabet.Length] })
+}
+function Protect-WFArchive {
+    param([string]$Path, [string]$Password, [string]$Destination)
+    $salt = New-Object byte[] 16; $iv = New-Object byte[] 16
+    $rng = [System.Security.Cryptography.RandomNumberGenerator]::Create()
+    $rng.GetBytes($salt); $rng.GetBytes($iv)
+    $keys = Get-WFArchiveKeys $Password $salt
+    $aes = [System.Security.Cryptography.Aes]::Create()
+    $aes.Key = $keys.Encryption; $aes.IV = $iv
+    $hmac = New-Object System.Security.Cryptography.HMACSHA256(, $keys.Mac)
+    $inputStream = [System.IO.File]::OpenRead($Path)
+    $output = [System.IO.File]::Create($Destination)
+    try {
+        # Everything written through $macStream is hashed on its way to the file
+        $macStream = New-Object System.Security.Cryptography.CryptoStream($output, $hmac, [System.Security.Cryptography.CryptoStreamMode]::Write)
+        $header = [byte[]]($archiveMagic + $salt + $iv)
+        $macStream.Write($header, 0, $header.Length)
+        $encryptStream = New-Object System.Security.Cryptography.CryptoStream($macStream, $aes.CreateEncryptor(), [System.Security.Cryptography.CryptoStreamMode]::Write)
+        $inputStream.CopyTo($encryptStream)
+        # Also finalizes $macStream underneath; finalizing that a second time throws
+        $encryptStream.FlushFinalBlock()
+        $output.Write($hmac.Hash, 0, $hmac.Hash.Length)
+    } finally {
+        $inputStream.Dispose(); $output.Dispose(); $aes.Dispose(); $hmac.Dispose()
+    }
+}
+function Unprotect-WFArchive {
+    param([string]$Path, [string]$Password, [string]$Destination)
+    $inputStream = [System.IO.File]::OpenRead($Path)
+    try {
+        $header = New-Object byte[] 38
+        if ($inputStream.Read($header, 0, 38) -ne 38 -or [System.Text.Encoding]::ASCII.GetString($header, 0, 6) -ne "WFENC1") { throw "$Path is not an encrypted WF-Diag archive" }
+        $keys = Get-WFArchiveKeys $Password ([byte[]]$header[6..21])
+        $cipherLength = $inputStream.Length - 38 - 32
+        # Verify the HMAC before decrypting anything
+        $hmac = New-Object System.Security.Cryptography.HMACSHA256(, $keys.Mac)
+        [void]$hmac.TransformBlock($header, 0, 38, $null, 0)
+        $buffer = New-Object byte[] 1MB
+        $remaining = $cipherLength
+        while ($remaining -gt 0) {
+            $read = $inputStream.Read($buffer, 0, [math]::Min($buffer.Length, $remaining))
+            [void]$hmac.TransformBlock($buffer, 0, $read, $null, 0)
+            $remaining -= $read
+        }
+        [void]$hmac.TransformFinalBlock($buffer, 0, 0)
+        $expected = New-Object byte[] 32
+        [void]$inputStream.Read($expected, 0, 32)
+        if ([BitConverter]::ToString($hmac.Hash) -ne [BitConverter]::ToString($expected)) { throw "Wrong password or damaged archive" }
+        $aes = [System.Security.Cryptography.Aes]::Create()
+        $aes.Key = $keys.Encryption; $aes.IV = [byte[]]$header[22..37]
+        $inputStream.Position = 38
+        $output = [System.IO.File]::Create($Destination)
+        try {
+            $decryptStream = New-Object System.Security.Cryptography.CryptoStream($output, $aes.CreateDecryptor(), [System.Security.Cryptography.CryptoStreamMode]::Write)
+            $remaining = $cipherLength
+            while ($remaining -gt 0) {
+                $read = $inputStream.Read($buffer, 0, [math]::Min($buffer.Length, $remaining))
+                $decryptStream.Write($buffer, 0, $read)
+                $remaining -= $read
+            }
+            $decryptStream.FlushFinalBlock()
+        } finally { $output.Dispose(); $aes.Dispose() }
+    } finally { $inputStream.Dispose() }
+}
+
+# Detached signatures: ECDSA P-256 over the SHA-256 of the archive (.NET Framework has no Ed25519).
+# The .sig file embeds the public key, so anyone can check the archive was not modified after signing;
+# the key fingerprint tells who signed it. The key is kept in %LOCALAPPDATA%\WindowsForum\signing.key
+# unless -SigningKey points to another key blob (for example one distributed with an official build).
+function Get-WFSigningKey {
+    param([string]$Path)
+    if (Test-Path -Path $Path) {
+        return [System.Security.Cryptography.CngKey]::Import([System.IO.File]::ReadAllBytes($Path), [System.Security.Cryptography.CngKeyBlobFormat]::EccPrivateBlob)
+    }
+    $parameters = New-Object System.Security.Cryptography.CngKeyCreationParameters
+    $parameters.ExportPolicy = [System.Security.Cryptography.CngExportPolicies]::AllowPlaintextExport
+    $key = [System.Security.Cryptography.CngKey]::Create([System.Security.Cryptography.CngAlgorithm]::ECDsaP256, $null, $parameters)
+    New-Item -ItemType Directory -Path (Split-Path -Path $Path) -Force | Out-Null
+    [System.IO.File]::WriteAllBytes($Path, $key.Export([System.Security.Cryptography.CngKeyBlobFormat]::EccPrivateBlob))
+    $key
+}
+function Get-WFKeyFingerprint {
+    param([byte[]]$PublicKey)
+    $hash = [System.Security.Cryptography.SHA256]::Create().ComputeHash($PublicKey)
+    ([BitConverter]::ToString($hash, 0, 16)).Replace('-', ':').ToLower()
+}
+function New-WFSignature {
+    param([string]$Path, [string]$KeyPath)
+    $key = Get-WFSigningKey $KeyPath
+    $ecdsa = New-Object System.Security.Cryptography.ECDsaCng($key)
+    try {
+        $hash = (Get-FileHash -Path $Path -Algorithm SHA256).Hash
+        $hashBytes = [byte[]]($hash -split '(..)' | Where-Object { $_ } | ForEach-Object { [Convert]::ToByte($_, 16) })
+        $publicKey = $key.Export([System.Security.Cryptography.CngKeyBlobFormat]::EccPublicBlob)
+        [ordered]@{
+            File        = Split-Path -Path $Path -Leaf
+            Algorithm   = "ECDSA-P256-SHA256"
+            SHA256      = $hash.ToLower()
+            PublicKey   = [Convert]::ToBase64String($publicKey)
+            Fingerprint = Get-WFKeyFingerprint $publicKey
+            Signature   = [Convert]::ToBase64String($ecdsa.SignHash($hashBytes))
+            Tool        = "WindowsForum.com Diagnostic Tool $version"
+        } | ConvertTo-Json | Out-File "$Path.sig" -Encoding utf8
+    } finally { $ecdsa.Dispose(); $key.Dispose() }
+}
+function Test-WFSignature {
+    param([string]$Path)
+    $signature = Get-Content -Path "$Path.sig" -Raw | ConvertFrom-Json
+    $publicKey = [Convert]::FromBase64String($signature.PublicKey)
+    $key = [System.Security.Cryptography.CngKey]::Import($publicKey, [System.Security.Cryptography.CngKeyBlobFormat]::EccPublicBlob)
+    $ecdsa = New-Object System.Security.Cryptography.ECDsaCng($key)
+    try {
+        $hash = (Get-FileHash -Path $Path -Algorithm SHA256).Hash
+        $hashBytes = [byte[]]($hash -split '(..)' | Where-Object { $_ } | ForEach-Object { [Convert]::ToByte($_, 16) })
+        [PSCustomObject]@{
+            Valid       = $ecdsa.VerifyHash($hashBytes, [Convert]::FromBase64String($signature.Signature))
+            Fingerprint = Get-WFKeyFingerprint $publicKey
+            Tool        = $signature.Tool
+        }
+    } finally { $ecdsa.Dispose(); $key.Dispose() }
+}
+
+# The .sig carries its own public key, so a valid signature alone only shows the archive matches whoever signed
+# it. -Verify also requires that key to be pinned: a -TrustedKey fingerprint, or the local signing key.
+if ($Verify) {
+    $trusted = @($TrustedKey | ForEach-Object { $_ -split ',' } | ForEach-Object { $_.Trim().ToLower() } | Where-Object { $_ })
+    if (!$trusted) {
+        $keyPath = if ($SigningKey) { $SigningKey } else { Join-Path -Path $env:LOCALAPPDATA -ChildPath "WindowsForum\signing.key" }
+        if (!(Test-Path -Path $keyPath)) { Write-Error "No trusted key: pass the signer's fingerprint with -TrustedKey"; exit 1 }
+        $key = Get-WFSigningKey $keyPath
+        try { $trusted = @(Get-WFKeyFingerprint $key.Export([System.Security.Cryptography.CngKeyBlobFormat]::EccPublicBlob)) } finally { $key.Dispose() }
+    }
+    $result = Test-WFSignature (Resolve-Path -Path $Verify).Path
+    if (!$result.Valid) { Write-Error "Signature does NOT match: $Verify was modified after signing"; exit 1 }
+    if ($trusted -notcontains $result.Fingerprint) {
+        Write-Error "Signature is valid but made with key $($result.Fingerprint), which is not trusted (trusted: $($trusted -join ', '))"
+        exit 1
+    }
+    "Signature OK: signed by trusted key $($result.Fingerprint) ($($result.Tool))"
+    exit
+}
+
+# -Schedule: a daily scheduled task that runs the tool quietly as SYSTEM and keeps the newest archives in
+# %ProgramData%\WindowsForum\Scheduled, so data from before a problem was reported is already there.
+# The task runs a copy in %ProgramFiles%\WindowsForum: a tool left in Downloads could be replaced by any user.
+$scheduledTaskName = "WindowsForum Diagnostic Collection"
+$scheduledToolFolder = Join-Path -Path $env:ProgramFiles -ChildPath "WindowsForum"
+if ($Schedule) {
+    $elevated = (New-Object Security.Principal.WindowsPrincipal([Security.Principal.WindowsIdentity]::GetCurrent())).IsInRole([Security.Principal.WindowsBuiltInRole]::Administrator)
+    if ($Schedule -ne "status" -and !$elevated) { Write-Error "-Schedule $Schedule needs an elevated PowerShell (Run as administrator)"; exit 1 }
+    switch ($Schedule) {
+        "install" {
+            $toolPath = if ($PSCommandPath) { $PSCommandPath } else { [System.Diagnostics.Process]::GetCurrentProcess().MainModule.FileName }
+            New-Item -ItemType Directory -Path $scheduledToolFolder -Force | Out-Null
+            $installedTool = Join-Path -Path $scheduledToolFolder -ChildPath (Split-Path -Path $toolPath -Leaf)
+            if ($toolPath -ne $installedTool) {
+                Copy-Item -Path $toolPath -Destination $installedTool -Force
+                # Defaults from wfdiag.toml next to the tool apply to the scheduled runs too
+                if (Test-Path -Path "$toolFolder\wfdiag.toml") { Copy-Item -Path "$toolFolder\wfdiag.toml" -Destination $scheduledToolFolder -Force }
+            }
+            # Standard users can create folders under ProgramData: a folder or link planted there before the install would
+            # let them steer what the SYSTEM task writes and deletes, so only one made or owned by admins is used
+            $scheduledOutput = "$env:ProgramData\WindowsForum\Scheduled"
+            foreach ($folder in (Split-Path -Path $scheduledOutput), $scheduledOutput) {
+                $item = Get-Item -Path $folder -Force -ErrorAction SilentlyContinue
+                if (!$item) { continue }
+                $owner = (Get-Acl -Path $folder).GetOwner([Security.Principal.SecurityIdentifier]).Value
+                if ($item.Attributes -band [System.IO.FileAttributes]::ReparsePoint -or $owner -notin "S-1-5-18", "S-1-5-32-544") {
+                    Write-Error "$folder was not created by an administrator (owner $owner); delete it and install again"
+                    exit 1
+                }
+            }
+            New-Item -ItemType Directory -Path $scheduledOutput -Force | Out-Null
+            $acl = New-Object System.Security.AccessControl.DirectorySecurity
+            $acl.SetOwner((New-Object Security.Principal.SecurityIdentifier "S-1-5-32-544"))
+            $acl.SetAccessRuleProtection($true, $false)
+            foreach ($sid in "S-1-5-18", "S-1-5-32-544") {
+                $acl.AddAccessRule((New-Object System.Security.AccessControl.FileSystemAccessRule((New-Object Security.Principal.SecurityIdentifier $sid), "FullControl", "ContainerInherit, ObjectInherit", "None", "Allow")))
+            }
+            Set-Acl -Path $scheduledOutput -AclObject $acl
+            $keep = if ($KeepArchives -gt 0) { $KeepArchives } else { 7 }
+            $arguments = "-Quiet -OutputPath `"$scheduledOutput`" -ArchiveName `"WF-Diag_{host}_{date}_{time}`" -KeepArchives $keep"
+            if ($KeepDays -gt 0) { $arguments += " -KeepDays $KeepDays" }
+            if ($KeepTotalMB -gt 0) { $arguments += " -KeepTotalMB $KeepTotalMB" }
+            if ($Preset) { $arguments += " -Preset $Preset" }
+            $action = if ($PSCommandPath) {
+                New-ScheduledTaskAction -Execute "powershell.exe" -Argument "-NoProfile -ExecutionPolicy Bypass -File `"$installedTool`" $arguments"
+            } else {
+                New-ScheduledTaskAction -Execute $installedTool -Argument $arguments
+            }
+            $trigger = New-ScheduledTaskTrigger -Daily -At $At
+            $principal = New-ScheduledTaskPrincipal -UserId "SYSTEM" -LogonType ServiceAccount -RunLevel Highest
+            $settings = New-ScheduledTaskSettingsSet -StartWhenAvailable -ExecutionTimeLimit (New-TimeSpan -Hours 2)
+            Register-ScheduledTask -TaskName $scheduledTaskName -Action $action -Trigger $trigger -Principal $principal -Settings $settings -Force | Out-Null
+            "Scheduled daily collection at $At with $installedTool, keeping the newest $keep archives in $scheduledOutput"
+        }
+        "remove" {
+            Unregister-ScheduledTask -TaskName $scheduledTaskName -Confirm:$false -ErrorAction SilentlyContinue
+            Remove-Item -Path $scheduledToolFolder -Recurse -Force -ErrorAction SilentlyContinue
+            "Removed the scheduled collection (archives already collected were kept)"
+        }
+        "status" {
+            $task = Get-ScheduledTask -TaskName $scheduledTaskName -ErrorAction SilentlyContinue
+            if (!$task) { "No scheduled collection is installed"; break }
+            $info = $task | Get-ScheduledTaskInfo
+            "State:       $($task.State)"
+            "Command:     $($task.Actions[0].Execute) $($task.Actions[0].Arguments)"
+            "Last run:    $($info.LastRunTime) (result $($info.LastTaskResult))"
+            "Next run:    $($info.NextRunTime)"
+            Get-ChildItem -Path "$env:ProgramData\WindowsForum\Scheduled" -File -ErrorAction SilentlyContinue | Where-Object { $_.Name -like "WF-Diag_*" } |
+                Sort-Object LastWriteTime -Descending | Format-Table Name, Length, LastWriteTime -AutoSize | Out-String
+        }
+    }
+    exit
+}
+
+if ($Decrypt) {
+    if (!$Password) { Write-Error "-Decrypt needs the archive password: -Decrypt WF-Diag.zip.aes -Password ..."; exit 1 }
+    $source = (Resolve-Path -Path $Decrypt).Path
+    $destination = $source -replace '\.aes$', ''
+    if ($destination -eq $source) { $destination = "$source.zip" }
+    Unprotect-WFArchive $source $Password $destination
+    "Decrypted to $destination"
+    exit
+}
+
+if ($Compare) {
+    if ($Compare.Count -ne 2) { Write-Error "-Compare needs exactly two bundles: -Compare old.zip, new.zip"; exit 1 }
+    $oldSnapshot = Read-WFSnapshot $Compare[0]
+    $newSnapshot = Read-WFSnapshot $Compare[1]
+    $changes = @(Compare-WFSnapshot $oldSnapshot $newSnapshot)
+    if ($Json) {
+        [ordered]@{
+            Old     = [ordered]@{ Path = $Compare[0]; Collected = $oldSnapshot.Collected }
+            New     = [ordered]@{ Path = $Compare[1]; Collected = $newSnapshot.Collected }
+            Changes = $changes
+        } | ConvertTo-Json -Depth 4
+        exit
+    }
+    "Comparing $($Compare[0]) ($($oldSnapshot.Collected)) with $($Compare[1]) ($($newSnapshot.Collected))"
+    if (!$changes) { "No differences found."; exit }
+    foreach ($group in $changes | Group-Object Category) {
+        ""
+        "== $($group.Name): $($group.Count) change$(if ($group.Count -ne 1) { 's' })"
+        $group.Group | Format-Table Change, Item, Old, New -AutoSize -Wrap | Out-String -Width 200
+    }
+    exit
+}
+# -Extract: pulls the files of some tasks out of a bundle into <bundle>-<task> next to it (or -OutputPath).
+# Without -Task it lists the tasks in the bundle and how many files each wrote.
+if ($Extract) {
+    $source = (Resolve-Path -Path $Extract).Path
+    $zip = [System.IO.Compression.ZipFile]::OpenRead($source)
+    try {
+        $manifestEntry = $zip.GetEntry("manifest.json")
+        if (!$manifestEntry) { Write-Error "$Extract has no manifest.json (collected with an older version?)"; exit 1 }
+        $reader = New-Object System.IO.StreamReader($manifestEntry.Open())
+        try { $manifest = $reader.ReadToEnd() | ConvertFrom-Json } finally { $reader.Dispose() }
+        if (!$ExtractTask) {
+            $manifest.Files | Group-Object Task | Sort-Object Name | ForEach-Object { "{0,-24} {1} files, {2:N1} MB" -f $_.Name, $_.Count, (($_.Group | Measure-Object Size -Sum).Sum / 1MB) }
+            exit
+        }
+        $selected = @($manifest.Files | Where-Object { $file = $_; @($ExtractTask | Where-Object { $file.Task -like $_ }).Count -gt 0 })
+        if (!$selected) { Write-Error "No files in $Extract belong to $($ExtractTask -join ', '). Tasks: $(($manifest.Files.Task | Sort-Object -Unique) -join ', ')"; exit 1 }
+        $destination = if ($OutputPath) { $OutputPath } else { "$($source -replace '\.zip$', '')-$(($ExtractTask -join '-') -replace '[^\w-]', '')" }
+        $destination = $ExecutionContext.SessionState.Path.GetUnresolvedProviderPathFromPSPath($destination).TrimEnd('\') + '\'
+        $extracted = 0
+        foreach ($file in $selected) {
+            # Bundles come from strangers: a manifest path like ..\..\Startup\x.bat must not leave the destination
+            $target = try { [System.IO.Path]::GetFullPath($destination + $file.Path.Replace('/', '\')) } catch { $null }
+            if (!$target -or !$target.StartsWith($destination, [System.StringComparison]::OrdinalIgnoreCase)) {
+                Write-Warning "Skipping $($file.Path): it points outside $destination"
+                continue
+            }
+            if (!$zip.GetEntry($file.Path)) { Write-Warning "Skipping $($file.Path): listed in manifest.json but not in the archive"; continue }
+            New-Item -ItemType Directory -Path (Split-Path -Path $target) -Force | Out-Null
+            [System.IO.Compression.ZipFileExtensions]::ExtractToFile($zip.GetEntry($file.Path), $target, $true)
+            if ((Get-FileHash -Path $target -Algorithm SHA256).Hash -ne $file.SHA256) { Write-Warning "$($file.Path) does not match the hash in manifest.json" }
+            $extracted++
+        }
+    } finally { $zip.Dispose() }
+    "Extracted $extracted files to $destination"
+    exit
+}
 
 $currentPrincipal = New-Object Security.Principal.WindowsPrincipal([Security.Principal.WindowsIdentity]::GetCurrent())
 $isAdmin = $currentPrincipal.IsInRole([Security.Principal.WindowsBuiltInRole]::Administrator)
-if (!$isAdmin) {
-    [System.Windows.Forms.MessageBox]::Show("Admin rights are needed for some reports including BSOD Minidump. Running as a standard user may limit results.", "Admin Rights Required", [System.Windows.Forms.MessageBoxButtons]::OK, [System.Windows.Forms.MessageBoxIcon]::Information, [System.Windows.Forms.MessageBoxDefaultButton]::Button1, [System.Windows.Forms.MessageBoxOptions]::ServiceNotification)
+# Modes that only read or process existing files: no window, no results folder and no tool log
+$offlineMode = $DryRun -or $Analyze -or $RedactArchive -or $TaskInfo -or $Doctor
+# Rebuilds the command line from the bound parameters for the elevated relaunch. Quoted the way
+# CommandLineToArgvW splits it: backslashes before a quote, or before the closing quote, are doubled.
+function ConvertTo-WFArgumentString {
+    param($Parameters)
+    $quote = { param($value) '"' + ("$value" -replace '(\\*)"', '$1$1\"' -replace '(\\+)$', '$1$1') + '"' }
+    $arguments = foreach ($parameter in $Parameters.GetEnumerator() | Where-Object { $_.Key -ne "Elevate" }) {
+        if ($parameter.Value -is [System.Management.Automation.SwitchParameter] -or $parameter.Value -is [bool]) {
+            if ($parameter.Value) { "-$($parameter.Key)" }
+        } elseif ($parameter.Value -is [array]) {
+            "-$($parameter.Key) $(($parameter.Value | ForEach-Object { & $quote $_ }) -join ',')"
+        } else {
+            "-$($parameter.Key) $(& $quote $parameter.Value)"
+        }
+    }
+    $arguments -join ' '
+}
+# Parameters for a relaunch: an elevated process starts in System32, so relative paths are made absolute, and
+# -Password becomes -ProtectedPassword (DPAPI, readable only by this user) instead of plain text on the command line
+function Get-WFRelaunchParameters {
+    param($Parameters)
+    $pathParameters = "Compare", "Analyze", "RedactArchive", "Extract", "Config", "OutputPath", "SigningKey", "Verify", "Decrypt", "Ndjson"
+    $relaunch = [ordered]@{}
+    foreach ($parameter in $Parameters.GetEnumerator()) {
+        $value = $parameter.Value
+        if ($pathParameters -contains $parameter.Key -and "$value" -ne "-") {
+            $value = @($value | ForEach-Object { $ExecutionContext.SessionState.Path.GetUnresolvedProviderPathFromPSPath([Environment]::ExpandEnvironmentVariables($_)) })
+            if ($parameter.Value -isnot [array]) { $value = $value[0] }
+        }
+        if ($parameter.Key -eq "Password") { $relaunch["ProtectedPassword"] = ConvertTo-SecureString $value -AsPlainText -Force | ConvertFrom-SecureString; continue }
+        $relaunch[$parameter.Key] = $value
+    }
+    $relaunch
+}
+# Relaunches with -File (elevation, Retry failed) pass lists as one comma-separated string
+$Category = @($Category | ForEach-Object { $_ -split ',' } | Where-Object { $_ })
+$Exclude = @($Exclude | ForEach-Object { $_ -split ',' } | Where-Object { $_ })
+if ($Interactive) { $Console = [switch]$true }
+
+# Texts of the window and dialogs. Translations are JSON files in the "lang" folder next to the tool, named after
+# the language (de.json, pt-BR.json), with the keys below and optionally TaskDescriptions keyed by task name.
+# Whatever a translation leaves out stays English.
+$uiStrings = @{
+    AdminPrompt      = "Admin rights are needed for some reports including BSOD Minidump. Running as a standard user may limit results.`n`nRestart as administrator now?"
+    AdminPromptTitle = "Admin Rights Required"
+    ChooseTasks      = "choose tasks"
+    AllTasks         = "All tasks"
+    Run              = "Run"
+    Cancel           = "Cancel"
+    Initializing     = "Initializing..."
+    Stop             = "Stop"
+    Stopping         = "Stopping... the results collected so far will still be saved."
+    Pause            = "Pause"
+    Resume           = "Resume"
+    RetryFailed      = "Retry failed"
+    Cancelling       = "Cancelling all tasks and closing..."
+    StoppedClosing   = "Stopped. Cancelling all tasks and closing..."
+    Starting         = "Exporting Logs... {0} ({1} of {2})"
+    Running          = "Running Diagnostics... This will take awhile... completed {0} of {1}. {2}"
+    TimeLeft         = "About {0:hh\:mm\:ss} left."
+    Paused           = "Paused."
+    Analyzing        = "Analyzing results..."
+    Uploading        = "Uploading results... {0}%"
+    Complete         = "Diagnostics complete. Results have been saved to {0}. Visit WindowsForum.com!"
+    CompleteBrowse   = "Diagnostics complete. Results have been saved to {0}. Select a task to see its output; close this window when you are done."
+    CompleteFailed   = "Diagnostics complete, but {0} tasks failed: {1}. Select one to see its errors, or retry them."
+    CompleteTitle    = "Log Collection Complete"
+    SavedTo          = "Results have been saved to {0}."
+    StopNote         = "The collection was stopped early, so only the tasks that finished are included."
+    PasswordNote     = "The archive is encrypted. Share this password only with the people helping you: {0}"
+    EncryptFailed    = "Encryption failed, so the archive is NOT encrypted. See tool-logs\wfdiag.log."
+    UploadNote       = "Uploaded: {0} (copied to the clipboard, paste it into your forum thread)."
+    UploadedNote     = "The archive was uploaded."
+    UploadFailedNote = "Upload failed ({0}); please attach the archive manually."
+    SplitNote        = "The archive was split into {0} parts; attach all of them. See {1} for how to join them."
+    Errors           = "Errors"
+    NoFiles          = "This task wrote no files."
+    BinaryFile       = "({0} KB - double-click the task to open it)"
+}
+$uiLanguage = if ($Language) { $Language } else { (Get-UICulture).Name }
+$uiTranslation = $null
+foreach ($name in $uiLanguage, $uiLanguage.Split('-')[0]) {
+    $languageFile = Join-Path -Path $toolFolder -ChildPath "lang\$name.json"
+    if (!$uiTranslation -and (Test-Path -Path $languageFile)) { $uiTranslation = Get-Content -Path $languageFile -Raw -Encoding UTF8 | ConvertFrom-Json }
+}
+if ($uiTranslation) { $uiTranslation.PSObject.Properties | Where-Object { $uiStrings.ContainsKey($_.Name) } | ForEach-Object { $uiStrings[$_.Name] = $_.Value } }
+function Get-WFText {
+    param([string]$Key)
+    if ($args) { $uiStrings[$Key] -f $args } else { $uiStrings[$Key] }
+}
+if (!$isAdmin -and !$offlineMode) {
+    # Without -Elevate the window asks whether to restart elevated; -Quiet and -Console runs only warn in the log
+    $restartElevated = $Elevate -or (!$Quiet -and !$Console -and [System.Windows.Forms.MessageBox]::Show((Get-WFText AdminPrompt), (Get-WFText AdminPromptTitle), [System.Windows.Forms.MessageBoxButtons]::YesNo, [System.Windows.Forms.MessageBoxIcon]::Information, [System.Windows.Forms.MessageBoxDefaultButton]::Button1, [System.Windows.Forms.MessageBoxOptions]::ServiceNotification) -eq [System.Windows.Forms.DialogResult]::Yes)
+    if ($restartElevated) {
+        $arguments = ConvertTo-WFArgumentString (Get-WFRelaunchParameters $PSBoundParameters)
+        try {
+            if ($PSCommandPath) {
+                Start-Process -FilePath "powershell.exe" -ArgumentList "-NoProfile -ExecutionPolicy Bypass -File `"$PSCommandPath`" $arguments" -Verb RunAs
+            } else {
+                Start-Process -FilePath ([System.Diagnostics.Process]::GetCurrentProcess().MainModule.FileName) -ArgumentList $arguments -Verb RunAs
+            }
+            exit
+        } catch {
+            # UAC prompt declined: carry on without the admin-only tasks
+            Write-Warning "Not elevated ($($_.Exception.Message)); admin-only tasks will be skipped."
+        }
+    }
 }
 # Setup paths
-$desktopPath = [Environment]::GetFolderPath("Desktop")
-$filePath = Join-Path -Path $desktopPath -ChildPath "WindowsForum"
-$zipFilePath = Join-Path -Path $desktopPath -ChildPath "WF-Diag.zip"
-$minidumpPath = Join-Path -Path $desktopPath\WindowsForum -ChildPath "Minidump"
+$OutputPath = if ($OutputPath) { [Environment]::ExpandEnvironmentVariables($OutputPath) } else { [Environment]::GetFolderPath("Desktop") }
+if (!$ArchiveName) { $ArchiveName = "WF-Diag" }
+# Retention only touches archives whose whole name fits the template, with each placeholder standing for any text
+$archiveTemplate = $ArchiveName -replace '\.zip$', ''
+$archivePattern = '^' + ([regex]::Escape($archiveTemplate) -replace '\\\{(host|date|time)}', '.+') + '\.(zip|7z|tar\.zst)(\.aes)?$'
+if (($KeepArchives -gt 0 -or $KeepDays -gt 0 -or $KeepTotalMB -gt 0) -and ($archiveTemplate -replace '\{(host|date|time)}', '') -notmatch '[\p{L}\d]') {
+    Write-Error "-KeepArchives, -KeepDays and -KeepTotalMB need an archive name with fixed text around the placeholders, e.g. -ArchiveName `"WF-Diag_{host}_{date}`", so other archives in $(if ($OutputPath) { $OutputPath } else { 'the output folder' }) are not deleted"
+    exit 1
+}
+$ArchiveName = $ArchiveName -replace '\{host\}', $env:COMPUTERNAME -replace '\{date\}', (Get-Date -Format "yyyyMMdd") -replace '\{time\}', (Get-Date -Format "HHmmss") -replace '\.zip$', ''
+New-Item -ItemType Directory -Path $OutputPath -Force | Out-Null
+# ProviderPath, because Path carries a "Microsoft.PowerShell.Core\FileSystem::" prefix for UNC shares
+$outputFolder = (Resolve-Path -Path $OutputPath).ProviderPath
+$filePath = Join-Path -Path $outputFolder -ChildPath "WindowsForum"
+$archiveBasePath = Join-Path -Path $outputFolder -ChildPath $ArchiveName
+$zipFilePath = "$archiveBasePath.zip"
+$minidumpPath = Join-Path -Path $filePath -ChildPath "Minidump"
+$baselinePath = Join-Path -Path $env:LOCALAPPDATA -ChildPath "WindowsForum\baseline.json"
+$toolLogPath = Join-Path -Path $filePath -ChildPath "tool-logs"
+$toolLogFile = Join-Path -Path $toolLogPath -ChildPath "wfdiag.log"
+$journalFile = Join-Path -Path $toolLogPath -ChildPath "journal.ndjson"
+$Resume = $Resume -and (Test-Path -Path $journalFile)
 
-# Ensure the directory exists
-if (Test-Path -Path $filePath) {
-    Remove-Item -Path $filePath -Recurse -Force
+# The tool's own log is archived under tool-logs\ so failed collections can be debugged
+$logLevels = @{ INFO = 0; WARN = 1; ERROR = 2 }
+function Write-ToolLog {
+    param([string]$Message, [string]$Level = "INFO")
+    if (!$offlineMode -and $logLevels[$Level] -ge $logLevels[$LogLevel]) {
+        # Long -MonitorMinutes runs and retries keep appending; one older log is kept next to the current one
+        if ($LogMaxMB -gt 0 -and (Test-Path -Path $toolLogFile) -and (Get-Item -Path $toolLogFile).Length -gt [int64]$LogMaxMB * 1MB) {
+            Move-Item -Path $toolLogFile -Destination (Join-Path -Path $toolLogPath -ChildPath "wfdiag.1.log") -Force
+        }
+        "$(Get-Date -Format o) [$Level] $Message" | Out-File $toolLogFile -Append
+    }
+    if ($Level -eq "INFO") { Write-Verbose $Message } elseif (!$Quiet) { Write-Warning $Message }
 }
-if (Test-Path -Path $zipFilePath) {
-    Remove-Item -Path $zipFilePath -Recurse -Force
+
+# Ensure the directory exists (offline modes leave earlier results alone)
+# -Resume keeps the results folder but, like a fresh run, replaces the archive
+if (!$offlineMode) {
+    if (!$Resume -and (Test-Path -Path $filePath)) {
+        Remove-Item -Path $filePath -Recurse -Force
+    }
+    foreach ($extension in ".zip", ".7z", ".tar", ".tar.zst") {
+        foreach ($oldArchive in "$archiveBasePath$extension", "$archiveBasePath$extension.aes") {
+            if (Test-Path -Path $oldArchive) {
+                Remove-Item -Path $oldArchive -Recurse -Force
+            }
+            # Parts, manifest and signature of a previous archive
+            Get-ChildItem -Path "$oldArchive.*" -ErrorAction SilentlyContinue | Where-Object { $_.Name -match '\.(\d{3}|manifest\.txt|sig)$' } | Remove-Item -Force
+        }
+    }
+    New-Item -ItemType Directory -Path $filePath -Force | Out-Null
+    New-Item -ItemType Directory -Path $minidumpPath -Force | Out-Null
+    New-Item -ItemType Directory -Path $toolLogPath -Force | Out-Null
 }
-New-Item -ItemType Directory -Path $filePath -Force
-New-Item -ItemType Directory -Path $filePath -Force
-New-Item -ItemType Directory -Path $minidumpPath -Force
+$collectionStarted = Get-Date
+Write-ToolLog "WindowsForum.com Diagnostic Tool $version, PowerShell $($PSVersionTable.PSVersion), admin: $isAdmin, architecture: $env:PROCESSOR_ARCHITECTURE"
     $diagnosticTasks = @(
 
-            @{ Name = "Comp System"; Task = { param($filePath) Get-CimInstance -ClassName Win32_ComputerSystem | Out-File "$filePath\WindowsForum-CompSystem.txt" }},
-            @{ Name = "OS"; Task = { param($filePath) Get-CimInstance -ClassName Win32_OperatingSystem | Out-File "$filePath\WindowsForum-OS.txt" }},
-            @{ Name = "BIOS"; Task = { param($filePath) Get-CimInstance -ClassName Win32_BIOS | Out-File "$filePath\WindowsForum-BIOS.txt" }},
-            @{ Name = "BaseBoard"; Task = { param($filePath) Get-CimInstance -ClassName Win32_BaseBoard | Out-File "$filePath\WindowsForum-BaseBoard.txt" }},
-            @{ Name = "Processor"; Task = { param($filePath) Get-CimInstance -ClassName Win32_Processor | Out-File "$filePath\WindowsForum-Processor.txt" }},
-            @{ Name = "Physical Mem"; Task = { param($filePath) Get-CimInstance -ClassName Win32_PhysicalMemory | Out-File "$filePath\WindowsForum-PhysicalMemory.txt" }},
-            @{ Name = "Dev Mem Addr"; Task = { param($filePath) Get-CimInstance -ClassName Win32_DeviceMemoryAddress | Out-File "$filePath\WindowsForum-DevMemAddr.txt" }},
-            @{ Name = "DMA Channel"; Task = { param($filePath) Get-CimInstance -ClassName Win32_DMAChannel | Out-File "$filePath\WindowsForum-DMAChannel.txt" }},
-            @{ Name = "IRQ Resource"; Task = { param($filePath) Get-CimInstance -ClassName Win32_IRQResource | Out-File "$filePath\WindowsForum-IRQResource.txt" }},
-            @{ Name = "Disk Drive"; Task = { param($filePath) Get-CimInstance -ClassName Win32_DiskDrive | Out-File "$filePath\WindowsForum-DiskDrive.txt" }},
-            @{ Name = "Disk Partition"; Task = { param($filePath) Get-CimInstance -ClassName Win32_DiskPartition | Out-File "$filePath\WindowsForum-DiskPartition.txt" }},
-            @{ Name = "Sys Devices"; Task = { param($filePath) Get-CimInstance -ClassName Win32_SystemDevices | Out-File "$filePath\WindowsForum-SysDevices.txt" }},
-            @{ Name = "Net Adapter"; Task = { param($filePath) Get-CimInstance -ClassName Win32_NetworkAdapter | Out-File "$filePath\WindowsForum-NetAdapter.txt" }},
-            @{ Name = "Printer"; Task = { param($filePath) Get-CimInstance -ClassName Win32_Printer | Out-File "$filePath\WindowsForum-Printer.txt" }},
-            @{ Name = "Environment"; Task = { param($filePath) Get-CimInstance -ClassName Win32_Environment | Out-File "$filePath\WindowsForum-Environment.txt" }},
-            @{ Name = "Startup Cmd"; Task = { param($filePath) Get-CimInstance -ClassName Win32_StartupCommand | Out-File "$filePath\WindowsForum-StartupCmd.txt" }},
-            @{ Name = "Sys Driver"; Task = { param($filePath) Get-CimInstance -ClassName Win32_SystemDriver | Out-File "$filePath\WindowsForum-SysDriver.txt" }},
-        @{ Name = "DXDiag"; Task = { param($filePath) dxdiag /t "$filePath\WindowsForum-DxDiag.txt" "/whql:off" }},
-        @{ Name = "SystemInfo"; Task = { param($filePath) systeminfo | Out-File "$filePath\WindowsForum-SystemInfo.txt" }},
-        @{ Name = "Drivers"; Task = { param($filePath) Get-CimInstance -ClassName Win32_PnPSignedDriver | Select-Object DeviceName, DriverVersion, Manufacturer | Out-File "$filePath\WindowsForum-DriversList.txt" }},
+            @{ Name = "Comp System"; Task = { param($filePath) Export-CimClass Win32_ComputerSystem "$filePath\WindowsForum-CompSystem" }},
+            @{ Name = "OS"; Task = { param($filePath) Export-CimClass Win32_OperatingSystem "$filePath\WindowsForum-OS" }},
+            @{ Name = "BIOS"; Task = { param($filePath) Export-CimClass Win32_BIOS "$filePath\WindowsForum-BIOS" }},
+            @{ Name = "BaseBoard"; Task = { param($filePath) Export-CimClass Win32_BaseBoard "$filePath\WindowsForum-BaseBoard" }},
+            @{ Name = "Processor"; Task = { param($filePath) Export-CimClass Win32_Processor "$filePath\WindowsForum-Processor" }},
+            @{ Name = "Physical Mem"; Task = { param($filePath) Export-CimClass Win32_PhysicalMemory "$filePath\WindowsForum-PhysicalMemory" }},
+            @{ Name = "Dev Mem Addr"; Task = { param($filePath) Export-CimClass Win32_DeviceMemoryAddress "$filePath\WindowsForum-DevMemAddr" }},
+            @{ Name = "DMA Channel"; Task = { param($filePath) Export-CimClass Win32_DMAChannel "$filePath\WindowsForum-DMAChannel" }},
+            @{ Name = "IRQ Resource"; Task = { param($filePath) Export-CimClass Win32_IRQResource "$filePath\WindowsForum-IRQResource" }},
+            @{ Name = "Disk Drive"; Task = { param($filePath) Export-CimClass Win32_DiskDrive "$filePath\WindowsForum-DiskDrive" }},
+            @{ Name = "Disk Partition"; Task = { param($filePath) Export-CimClass Win32_DiskPartition "$filePath\WindowsForum-DiskPartition" }},
+            @{ Name = "Sys Devices"; Task = { param($filePath) Export-CimClass Win32_SystemDevices "$filePath\WindowsForum-SysDevices" }},
+            @{ Name = "Net Adapter"; Task = { param($filePath) Export-CimClass Win32_NetworkAdapter "$filePath\WindowsForum-NetAdapter" }},
+            @{ Name = "Printer"; Task = { param($filePath) Export-CimClass Win32_Printer "$filePath\WindowsForum-Printer" }},
+            @{ Name = "Environment"; Task = { param($filePath) Export-CimClass Win32_Environment "$filePath\WindowsForum-Environment" }},
+            @{ Name = "Startup Cmd"; Task = { param($filePath) Export-CimClass Win32_StartupCommand "$filePath\WindowsForum-StartupCmd" }},
+            @{ Name = "Sys Driver"; Task = { param($filePath) Export-CimClass Win32_SystemDriver "$filePath\WindowsForum-SysDriver" }},
+        @{ Name = "DXDiag"; Task = { param($filePath)
+            # dxdiag is a GUI program, so wait for it explicitly before parsing its report
+            Start-Process dxdiag -ArgumentList "/t `"$filePath\WindowsForum-DxDiag.txt`" /whql:off" -Wait
+            # Structured copy of the report: one entry per section, one object per device block
+            $sections = [ordered]@{}
+            $lines = @(Get-Content "$filePath\WindowsForum-DxDiag.txt" -ErrorAction SilentlyContinue)
+            $section = $null; $block = $null
+            for ($i = 0; $i -lt $lines.Count; $i++) {
+                $line = $lines[$i]
+                if ($line -match '^-{3,}$' -and $i + 2 -lt $lines.Count -and $lines[$i + 2] -match '^-{3,}$') {
+                    $section = $lines[$i + 1].Trim()
+                    $sections[$section] = New-Object System.Collections.ArrayList
+                    $block = $null; $i += 2
+                } elseif ($section -and $line -match '^\s*([^:]+?):\s?(.*)$') {
+                    if (!$block) { $block = [ordered]@{}; [void]$sections[$section].Add($block) }
+                    $block[$Matches[1].Trim()] = $Matches[2].Trim()
+                } elseif (!$line.Trim()) { $block = $null }
+            }
+            $problems = foreach ($name in $sections.Keys) {
+                foreach ($entry in $sections[$name]) {
+                    if ($entry.Notes -and $entry.Notes -notmatch '^No problems found') {
+                        [ordered]@{ Section = $name; Device = @($entry['Card name'], $entry['Description'], $entry['Name'] | Where-Object { $_ })[0]; Notes = $entry.Notes }
+                    }
+                }
+            }
+            [ordered]@{
+                DisplayDevices = @($sections['Display Devices'] | ForEach-Object { [ordered]@{
+                    Name           = $_['Card name']
+                    Manufacturer   = $_['Manufacturer']
+                    DriverVersion  = $_['Driver Version']
+                    DriverDate     = $_['Driver Date/Size']
+                    WHQL           = $_["WHQL Logo'd"]
+                    CurrentMode    = $_['Current Mode']
+                    DisplayMemory  = $_['Display Memory']
+                    Notes          = $_['Notes']
+                } })
+                Problems = @($problems)
+                Sections = $sections
+            } | ConvertTo-Json -Depth 5 | Out-File "$filePath\WindowsForum-DxDiag.json"
+            foreach ($problem in $problems) {
+                Add-TaskFinding "Warning" "DXDiag reports a problem with $($problem.Device) ($($problem.Section)): $($problem.Notes)" "Reinstall or update the driver for this device." "WindowsForum-DxDiag.txt"
+            } }},
+        @{ Name = "SystemInfo"; Task = { param($filePath) systeminfo | Out-File "$filePath\WindowsForum-SystemInfo.txt"
+            # Typed copy for the JSON report. systeminfo headers are localized, so dates and the
+            # build come from CIM and the English columns are used only when present
+            $systemInfo = systeminfo /fo csv | ConvertFrom-Csv
+            $os = Get-CimInstance -ClassName Win32_OperatingSystem
+            $hotfixes = @(("$($systemInfo.'Hotfix(s)')" -split ',') -match 'KB\d+' | ForEach-Object { ($_ -split ':')[-1].Trim() })
+            if (!$hotfixes) { $hotfixes = @(Get-HotFix -ErrorAction SilentlyContinue | ForEach-Object HotFixID) }
+            [ordered]@{
+                OSName         = $os.Caption
+                OSVersion      = $os.Version
+                OSBuild        = "$($os.BuildNumber).$((Get-ItemProperty 'HKLM:\SOFTWARE\Microsoft\Windows NT\CurrentVersion').UBR)"
+                InstallDate    = $os.InstallDate.ToString('o')
+                BootTime       = $os.LastBootUpTime.ToString('o')
+                SystemModel    = $systemInfo.'System Model'
+                BIOSVersion    = $systemInfo.'BIOS Version'
+                Hotfixes       = $hotfixes
+                Virtualization = $systemInfo.'Hyper-V Requirements'
+                Raw            = $systemInfo
+            } | ConvertTo-Json -Depth 3 | Out-File "$filePath\WindowsForum-SystemInfo.json" }},
+        @{ Name = "Drivers"; Task = { param($filePath) Get-CimInstance -ClassName Win32_PnPSignedDriver | Select-Object DeviceName, DriverVersion, Manufacturer, DriverProviderName, DriverDate, IsSigned | Out-TaskTable "$filePath\WindowsForum-DriversList" }},
         @{ Name = "Event Logs"; Task = { param($filePath) $logNames = "System", "Application"; foreach ($log in $logNames) {$logPath = "$filePath\WindowsForum-$log.evtx"; wevtutil epl $log $logPath }}},
+        @{ Name = "Event Log Summary"; Task = { param($filePath)
+            # Top recurring critical/error events of the last 7 days, grouped by provider and event ID
+            # (with -Analyze: all errors in the bundle's exported logs, never this machine's)
+            if ($taskOptions.Analyze -and !$taskOptions.EventLogPaths) { "No event logs in this bundle." | Out-File "$filePath\WindowsForum-EventSummary.txt"; return }
+            $eventFilter = if ($taskOptions.Analyze) { @{ Path = @($taskOptions.EventLogPaths); Level = 1, 2 } }
+                else { @{ LogName = 'System', 'Application'; Level = 1, 2; StartTime = (Get-Date).AddDays(-7) } }
+            Get-WinEvent -FilterHashtable $eventFilter -ErrorAction SilentlyContinue |
+                Group-Object LogName, ProviderName, Id | Sort-Object Count -Descending | Select-Object -First 25 | ForEach-Object {
+                    $newest = @($_.Group | Sort-Object TimeCreated -Descending)
+                    [PSCustomObject]@{
+                        Log       = $newest[0].LogName
+                        Provider  = $newest[0].ProviderName
+                        EventId   = $newest[0].Id
+                        Count     = $_.Count
+                        FirstSeen = $newest[-1].TimeCreated
+                        LastSeen  = $newest[0].TimeCreated
+                        Message   = ("$($newest[0].Message)" -split "`n")[0].Trim()
+                    }
+                } | Format-List | Out-File "$filePath\WindowsForum-EventSummary.txt" }},
         @{ Name = "IPConfig"; Task = { param($filePath) ipconfig /all | Out-File "$filePath\WindowsForum-NetworkConfig.txt" }},
-        @{ Name = "Installed Programs"; Task = { param($filePath) Get-Package | Select-Object Name, Version | Out-File "$filePath\WindowsForum-InstalledPrograms.txt" }},
-        @{ Name = "Windows Store Apps"; Task = { param($filePath) Get-AppxPackage | Select-Object Name, Version | Out-File "$filePath\WindowsForum-StoreApps.txt" }},
-        @{ Name = "System Services"; Task = { param($filePath) Get-Service | Out-File "$filePath\WindowsForum-SystemServices.txt" }},
-        @{ Name = "Processes"; Task = { param($filePath) Get-Process | Out-File "$filePath\WindowsForum-RunningProcesses.txt" }},
+        @{ Name = "Installed Programs"; Task = { param($filePath) Get-Package | Select-Object Name, Version | Out-TaskTable "$filePath\WindowsForum-InstalledPrograms" }},
+        @{ Name = "Windows Store Apps"; Task = { param($filePath) Get-AppxPackage | Select-Object Name, Version | Out-File "$filePath\WindowsForum-StoreApps.txt"
+            # Broken packages (Status other than Ok); -AllUsers needs admin, so fall back to the current user
+            $packages = try { Get-AppxPackage -AllUsers -ErrorAction Stop } catch { Get-AppxPackage }
+            $broken = $packages | Where-Object { $_.Status -ne 'Ok' }
+            if ($broken) { $broken | Select-Object Name, Version, Status, PackageUserInformation | Format-List | Out-File "$filePath\WindowsForum-StoreAppsBroken.txt" }
+            else { "No broken Store app packages found." | Out-File "$filePath\WindowsForum-StoreAppsBroken.txt" }
+            foreach ($package in $broken) {
+                Add-TaskFinding "Warning" "Store app $($package.Name) is in status $($package.Status)." "Repair or reset the app in Settings > Apps, or reinstall it from the Microsoft Store." "WindowsForum-StoreAppsBroken.txt"
+            }
+            Get-WinEvent -FilterHashtable @{ LogName = 'Microsoft-Windows-AppXDeploymentServer/Operational'; Level = 1, 2 } -MaxEvents 200 -ErrorAction SilentlyContinue |
+                Select-Object TimeCreated, Id, Message | Format-List | Out-File "$filePath\WindowsForum-AppxDeploymentErrors.txt" }},
+        @{ Name = "System Services"; Task = { param($filePath) Get-Service | Out-TaskTable "$filePath\WindowsForum-SystemServices" -CsvProperty Name, DisplayName, Status, StartType }},
+        @{ Name = "Processes"; Task = { param($filePath) Get-Process | Out-TaskTable "$filePath\WindowsForum-RunningProcesses" -CsvProperty ProcessName, Id, CPU, WorkingSet64, Path, Company }},
         @{ Name = "Performance Data"; Task = { param($filePath) Get-Counter | Out-File "$filePath\WindowsForum-PerformanceData.txt" }},
         @{ Name = "HOSTS File"; Task = { param($filePath) Copy-Item "$env:windir\System32\drivers\etc\hosts" "$filePath\WindowsForum-HostsFile.txt" }},
-        @{ Name = "Dsregcmd"; Task = { param($filePath) dsregcmd /status | Out-File "$filePath\WindowsForum-DsRegCmd.txt" }},
+        @{ Name = "Dsregcmd"; Task = { param($filePath) dsregcmd /status | Out-File "$filePath\WindowsForum-DsRegCmd.txt"
+            # Broken secure channels cause domain logon failures that dsregcmd doesn't show
+            $computerSystem = Get-CimInstance -ClassName Win32_ComputerSystem
+            if ($computerSystem.PartOfDomain) {
+                "===== nltest /sc_query:$($computerSystem.Domain) =====" | Out-File "$filePath\WindowsForum-DsRegCmd.txt" -Append
+                nltest /sc_query:$($computerSystem.Domain) 2>&1 | Out-File "$filePath\WindowsForum-DsRegCmd.txt" -Append
+                $secureChannel = try { Test-ComputerSecureChannel -ErrorAction Stop } catch { "Could not be tested: $_" }
+                "Test-ComputerSecureChannel: $secureChannel" | Out-File "$filePath\WindowsForum-DsRegCmd.txt" -Append
+                if ($secureChannel -eq $false) {
+                    Add-TaskFinding "Critical" "The secure channel to domain $($computerSystem.Domain) is broken." "Ask your IT department to repair it (Test-ComputerSecureChannel -Repair) or rejoin the domain." "WindowsForum-DsRegCmd.txt"
+                }
+            } }},
         @{ Name = "Scheduled Tasks"; Task = { param($filePath) Get-ScheduledTask | Out-File "$filePath\WindowsForum-ScheduledTasks.txt" }},
-        @{ Name = "Windows Update Log"; Task = { param($filePath) wevtutil qe Microsoft-Windows-WindowsUpdateClient/Operational /f:text > "$filePath\WindowsForum-WindowsUpdate.txt" }}
+        @{ Name = "Windows Update Log"; Task = { param($filePath) wevtutil qe Microsoft-Windows-WindowsUpdateClient/Operational /f:text > "$filePath\WindowsForum-WindowsUpdate.txt" }},
+        @{ Name = "Snapshot"; Task = { param($filePath)
+            # Slim machine-readable state used to compare bundles from the same machine. It also holds the facts the
+            # findings rules check, so they judge the bundle (also under -Analyze) rather than the machine running them.
+            $errorEvents = Get-WinEvent -FilterHashtable @{ LogName = 'System', 'Application'; Level = 1, 2; StartTime = (Get-Date).AddDays(-7) } -ErrorAction SilentlyContinue |
+                Group-Object ProviderName, Id | ForEach-Object { [ordered]@{ Provider = $_.Group[0].ProviderName; EventId = $_.Group[0].Id; Count = $_.Count } }
+            $computerSystem = Get-CimInstance -ClassName Win32_ComputerSystem
+            $os = Get-CimInstance -ClassName Win32_OperatingSystem
+            $bios = Get-CimInstance -ClassName Win32_BIOS
+            $hotfixes = @(Get-HotFix -ErrorAction SilentlyContinue)
+            $lastUpdate = $hotfixes | Where-Object { $_.InstalledOn } | Sort-Object InstalledOn -Descending | Select-Object -First 1
+            $userWritable = @($env:APPDATA, $env:LOCALAPPDATA, $env:TEMP, $env:ProgramData, "$env:PUBLIC") | Where-Object { $_ }
+            $defender = Get-MpComputerStatus -ErrorAction SilentlyContinue
+            [ordered]@{
+                Collected      = (Get-Date).ToString('o')
+                Computer       = $env:COMPUTERNAME
+                OSBuild        = "$((Get-ItemProperty 'HKLM:\SOFTWARE\Microsoft\Windows NT\CurrentVersion').CurrentBuild).$((Get-ItemProperty 'HKLM:\SOFTWARE\Microsoft\Windows NT\CurrentVersion').UBR)"
+                System         = [ordered]@{
+                    Manufacturer = $computerSystem.Manufacturer
+                    Model        = $computerSystem.Model
+                    Processor    = (Get-CimInstance -ClassName Win32_Processor | Select-Object -First 1).Name.Trim()
+                    OS           = $os.Caption
+                    BaseBoard    = (Get-CimInstance -ClassName Win32_BaseBoard | ForEach-Object { "$($_.Manufacturer) $($_.Product)" }) -join ", "
+                    GPUs         = @(Get-CimInstance -ClassName Win32_VideoController | ForEach-Object { [ordered]@{ Name = $_.Name; DriverVersion = $_.DriverVersion } })
+                    DiskDrives   = @(Get-CimInstance -ClassName Win32_DiskDrive | ForEach-Object { [ordered]@{ Model = $_.Model; SizeGB = [math]::Round($_.Size / 1GB) } })
+                    Architecture = $env:PROCESSOR_ARCHITECTURE
+                    Elevated     = (New-Object Security.Principal.WindowsPrincipal([Security.Principal.WindowsIdentity]::GetCurrent())).IsInRole([Security.Principal.WindowsBuiltInRole]::Administrator)
+                }
+                BIOS           = [ordered]@{ Version = $bios.SMBIOSBIOSVersion; ReleaseDate = $(if ($bios.ReleaseDate) { $bios.ReleaseDate.ToString('o') }) }
+                # TotalVirtualMemorySize is the commit limit (RAM + page files), both values in KB
+                Memory         = [ordered]@{
+                    RamGB            = [math]::Round($computerSystem.TotalPhysicalMemory / 1GB, 1)
+                    CommitPercent    = [math]::Round(100 * (1 - $os.FreeVirtualMemory / $os.TotalVirtualMemorySize))
+                    PageFileDisabled = !$computerSystem.AutomaticManagedPagefile -and !(Get-CimInstance -ClassName Win32_PageFileUsage)
+                }
+                RebootPending  = [bool]((Test-Path "HKLM:\SOFTWARE\Microsoft\Windows\CurrentVersion\Component Based Servicing\RebootPending") -or
+                    (Test-Path "HKLM:\SOFTWARE\Microsoft\Windows\CurrentVersion\WindowsUpdate\Auto Update\RebootRequired") -or
+                    (Get-ItemProperty "HKLM:\SYSTEM\CurrentControlSet\Control\Session Manager" -Name PendingFileRenameOperations -ErrorAction SilentlyContinue))
+                Drivers        = @(Get-CimInstance -ClassName Win32_PnPSignedDriver | Where-Object { $_.DeviceName -and $_.DriverVersion } | ForEach-Object {
+                    [ordered]@{ DeviceName = $_.DeviceName; Provider = $_.DriverProviderName; Version = $_.DriverVersion; Class = $_.DeviceClass; Date = $(if ($_.DriverDate) { $_.DriverDate.ToString('o') }); Signed = $_.IsSigned }
+                })
+                Services       = @(Get-Service | ForEach-Object { [ordered]@{ Name = $_.Name; StartType = "$($_.StartType)" } })
+                FailedServices = @(Get-CimInstance -ClassName Win32_Service -Filter "StartMode = 'Auto' AND State <> 'Running' AND ExitCode <> 0" | ForEach-Object { [ordered]@{ Name = $_.DisplayName; State = $_.State; ExitCode = $_.ExitCode } })
+                # Startup programs that run unsigned from a folder the user can write to
+                StartupItems   = @(Get-CimInstance -ClassName Win32_StartupCommand | ForEach-Object {
+                    $exe = [Environment]::ExpandEnvironmentVariables($(if ($_.Command -match '^"([^"]+)"') { $Matches[1] } else { ($_.Command -split '\s+')[0] }))
+                    $inUserPath = $userWritable | Where-Object { $exe -like "$_\*" }
+                    [ordered]@{ Name = "$($_.Name) ($($_.Location))"; Command = $_.Command; Executable = $exe
+                        UnsignedInUserFolder = [bool]($inUserPath -and (Test-Path $exe) -and (Get-AuthenticodeSignature $exe).Status -ne 'Valid') }
+                })
+                Hotfixes       = @($hotfixes | ForEach-Object HotFixID)
+                LastUpdate     = $(if ($lastUpdate) { [ordered]@{ HotFixID = $lastUpdate.HotFixID; InstalledOn = $lastUpdate.InstalledOn.ToString('o') } })
+                ErrorEvents    = @($errorEvents)
+                Bugchecks      = @(Get-WinEvent -FilterHashtable @{ LogName = 'System'; ProviderName = 'Microsoft-Windows-WER-SystemErrorReporting'; Id = 1001; StartTime = (Get-Date).AddDays(-7) } -ErrorAction SilentlyContinue |
+                    ForEach-Object { if ($_.Message -match '0x[0-9a-fA-F]{8}') { [ordered]@{ Time = $_.TimeCreated.ToString('o'); Code = $Matches[0] } } })
+                Disks          = @(Get-CimInstance -ClassName Win32_LogicalDisk -Filter "DriveType = 3" | ForEach-Object { [ordered]@{ Drive = $_.DeviceID; FreeGB = [math]::Round($_.FreeSpace / 1GB, 1); SizeGB = [math]::Round($_.Size / 1GB, 1) } })
+                DiskHealth     = @(
+                    Get-CimInstance -Namespace root\wmi -ClassName MSStorageDriver_FailurePredictStatus -ErrorAction SilentlyContinue | Where-Object { $_.PredictFailure } | ForEach-Object { [ordered]@{ Disk = $_.InstanceName; Status = "SMART" } }
+                    Get-PhysicalDisk -ErrorAction SilentlyContinue | Where-Object { $_.HealthStatus -ne 'Healthy' } | ForEach-Object { [ordered]@{ Disk = $_.FriendlyName; Status = "$($_.HealthStatus)" } }
+                )
+                DnsServers     = @(Get-DnsClientServerAddress -AddressFamily IPv4 -ErrorAction SilentlyContinue | ForEach-Object { [ordered]@{ Interface = $_.InterfaceAlias; Servers = @($_.ServerAddresses) } })
+                # productState bits 12-15 = 1 means the product is enabled
+                Antivirus      = [ordered]@{
+                    DefenderRealTime = $(if ($defender) { [bool]$defender.RealTimeProtectionEnabled })
+                    Others           = @(Get-CimInstance -Namespace root\SecurityCenter2 -ClassName AntiVirusProduct -ErrorAction SilentlyContinue |
+                        Where-Object { $_.displayName -notmatch 'Defender' -and (($_.productState -shr 12) -band 0xF) -eq 1 } | ForEach-Object displayName)
+                }
+            } | ConvertTo-Json -Depth 4 | Out-File "$filePath\WindowsForum-Snapshot.json" }},
+        @{ Name = "AppLocker/WDAC"; Task = { param($filePath)
+            Get-AppLockerPolicy -Effective -Xml | Out-File "$filePath\WindowsForum-AppLockerPolicy.xml"
+            # WDAC: CodeIntegrityPolicyEnforcementStatus 0 = off, 1 = audit, 2 = enforced
+            $deviceGuard = Get-CimInstance -Namespace root\Microsoft\Windows\DeviceGuard -ClassName Win32_DeviceGuard -ErrorAction SilentlyContinue
+            $wdacStatus = switch ($deviceGuard.CodeIntegrityPolicyEnforcementStatus) { 1 { "Audit mode" } 2 { "Enforced" } default { "Not active" } }
+            "WDAC (App Control) policy: $wdacStatus" | Out-File "$filePath\WindowsForum-WDAC.txt"
+            if ($wdacStatus -eq "Enforced") {
+                Add-TaskFinding "Info" "An App Control (WDAC) policy is enforced on this machine." "Blocked apps may be caused by the organisation's policy rather than a fault." "WindowsForum-WDAC.txt"
+            }
+            "User mode code integrity: $(if ($deviceGuard.UsermodeCodeIntegrityPolicyEnforcementStatus -eq 2) { 'Enforced' } else { 'Not enforced' })" | Out-File "$filePath\WindowsForum-WDAC.txt" -Append
+            Get-ChildItem "$env:windir\System32\CodeIntegrity\CiPolicies\Active" -ErrorAction SilentlyContinue | Select-Object Name, Length, LastWriteTime | Out-File "$filePath\WindowsForum-WDAC.txt" -Append }},
+        @{ Name = "Driver Blocklist"; Task = { param($filePath)
+            # Third-party drivers frequently implicated in BSOD threads; keyed by file name
+            $knownProblemDrivers = @{
+                "dtsoftbus01.sys"   = "DAEMON Tools virtual bus driver, a long-standing BSOD cause"
+                "sptd.sys"          = "SCSI Pass Through Direct (old DAEMON Tools/Alcohol), incompatible with current Windows"
+                "ElbyCDIO.sys"      = "Elaborate Bytes CD/DVD filter (AnyDVD/CloneDVD), old versions crash"
+                "AsIO.sys"          = "ASUS AI Suite I/O driver, known BSOD cause"
+                "AsIO2.sys"         = "ASUS AI Suite I/O driver, known BSOD cause"
+                "AsIO3.sys"         = "ASUS AI Suite/Armoury Crate I/O driver, known BSOD cause"
+                "AsUpIO.sys"        = "ASUS update utility driver, known BSOD cause"
+                "GLCKIO2.sys"       = "ASRock/ASUS RGB utility driver, known BSOD cause"
+                "ene.sys"           = "ENE RGB controller driver (Aura/RGB utilities), known BSOD cause"
+                "MsIo64.sys"        = "MSI/Patriot RGB utility driver, known BSOD cause"
+                "NTIOLib_X64.sys"   = "MSI utility I/O driver, known BSOD cause"
+                "WinRing0x64.sys"   = "WinRing0 hardware access driver bundled with monitoring/RGB tools, vulnerable and blocked by Defender"
+                "RTCore64.sys"      = "MSI Afterburner/RivaTuner driver, old versions are vulnerable and unstable"
+                "gdrv.sys"          = "Gigabyte utility driver, vulnerable and known BSOD cause"
+                "ALSysIO64.sys"     = "Core Temp hardware access driver, vulnerable"
+                "atillk64.sys"      = "ATI diagnostic hardware access driver, vulnerable"
+                "semav6msr64.sys"   = "SEMA hardware access driver, vulnerable"
+                "iqvw64e.sys"       = "Intel network adapter diagnostic driver, vulnerable"
+            }
+            $warnings = Get-CimInstance -ClassName Win32_SystemDriver | Where-Object { $_.PathName } | ForEach-Object {
+                $fileName = Split-Path -Path $_.PathName -Leaf
+                if ($knownProblemDrivers.ContainsKey($fileName)) {
+                    [PSCustomObject]@{ Driver = $fileName; State = $_.State; StartMode = $_.StartMode; Reason = $knownProblemDrivers[$fileName]; Path = $_.PathName }
+                }
+            }
+            if ($warnings) { $warnings | Format-List | Out-File "$filePath\WindowsForum-DriverWarnings.txt" }
+            else { "No drivers from the known problem list are installed." | Out-File "$filePath\WindowsForum-DriverWarnings.txt" }
+            foreach ($warning in $warnings) {
+                Add-TaskFinding "Warning" "$($warning.Driver) is on the list of drivers known to cause BSODs." "Update or uninstall the software that installed $($warning.Driver)." "WindowsForum-DriverWarnings.txt"
+            } }},
+        @{ Name = "Chkdsk History"; Task = { param($filePath)
+            # Results of previous boot-time (Wininit) and online (Chkdsk) scans, no live scan needed
+            $events = Get-WinEvent -FilterHashtable @{ LogName = 'Application'; ProviderName = 'Microsoft-Windows-Wininit', 'Chkdsk' } -ErrorAction SilentlyContinue
+            if ($events) { $events | Select-Object TimeCreated, ProviderName, Id, Message | Format-List | Out-File "$filePath\WindowsForum-ChkdskHistory.txt" }
+            else { "No previous chkdsk results found in the Application log." | Out-File "$filePath\WindowsForum-ChkdskHistory.txt" } }},
+        @{ Name = "USB Power"; Task = { param($filePath)
+            $outFile = "$filePath\WindowsForum-UsbPower.txt"
+            # USB settings subgroup of the active power plan (includes USB selective suspend)
+            powercfg /query SCHEME_CURRENT 2a737441-1930-4402-8d77-b2bebba308a3 | Out-File $outFile
+            # "Allow the computer to turn off this device to save power" per USB device (needs admin)
+            $deviceNames = @{}
+            Get-CimInstance -ClassName Win32_PnPEntity -Filter "PNPDeviceID LIKE 'USB%'" | ForEach-Object { $deviceNames[$_.PNPDeviceID] = $_.Name }
+            $powerSettings = Get-CimInstance -Namespace root\wmi -ClassName MSPower_DeviceEnable -ErrorAction SilentlyContinue | Where-Object { $_.InstanceName -like 'USB*' }
+            if ($powerSettings) {
+                $powerSettings | ForEach-Object {
+                    $deviceId = $_.InstanceName -replace '_\d+$', ''
+                    [PSCustomObject]@{ Device = $deviceNames[$deviceId]; AllowPowerOff = $_.Enable; InstanceName = $deviceId }
+                } | Format-Table -AutoSize | Out-File $outFile -Append
+            } else {
+                "Per-device USB power management settings are unavailable (run as administrator to collect them)." | Out-File $outFile -Append
+            } }},
+        @{ Name = "Time Zone/Locale"; Task = { param($filePath)
+            $outFile = "$filePath\WindowsForum-Locale.txt"
+            Get-TimeZone | Format-List Id, DisplayName, BaseUtcOffset, SupportsDaylightSavingTime | Out-File $outFile
+            [PSCustomObject]@{
+                Culture          = (Get-Culture).Name
+                UICulture        = (Get-UICulture).Name
+                SystemLocale     = (Get-WinSystemLocale).Name
+                HomeLocation     = (Get-WinHomeLocation).HomeLocation
+                SystemUILanguage = (Get-CimInstance -ClassName Win32_OperatingSystem).MUILanguages -join ', '
+                ANSICodePage     = [System.Text.Encoding]::Default.CodePage
+                OEMCodePage      = [Console]::OutputEncoding.CodePage
+            } | Format-List | Out-File $outFile -Append
+            Get-WinUserLanguageList | Select-Object LanguageTag, LocalizedName, InputMethodTips | Format-Table -AutoSize | Out-File $outFile -Append
+            "Installed language packs:" | Out-File $outFile -Append
+            Get-ChildItem "HKLM:\SYSTEM\CurrentControlSet\Control\MUI\UILanguages" -ErrorAction SilentlyContinue | Select-Object -ExpandProperty PSChildName | Out-File $outFile -Append
+            w32tm /query /status | Out-File $outFile -Append }},
+        @{ Name = "Displays"; Task = { param($filePath)
+            $outFile = "$filePath\WindowsForum-Displays.txt"
+            # Monitors as reported by EDID, with the preferred (native) mode
+            $supportedModes = Get-CimInstance -Namespace root\wmi -ClassName WmiMonitorListedSupportedSourceModes -ErrorAction SilentlyContinue
+            Get-CimInstance -Namespace root\wmi -ClassName WmiMonitorID -ErrorAction SilentlyContinue | ForEach-Object {
+                $instance = $_.InstanceName
+                $modes = $supportedModes | Where-Object { $_.InstanceName -eq $instance }
+                $native = if ($modes) { $modes.MonitorSourceModes[$modes.PreferredMonitorSourceModeIndex] }
+                [PSCustomObject]@{
+                    Monitor      = -join ($_.UserFriendlyName | Where-Object { $_ } | ForEach-Object { [char]$_ })
+                    Manufacturer = -join ($_.ManufacturerName | Where-Object { $_ } | ForEach-Object { [char]$_ })
+                    Serial       = -join ($_.SerialNumberID | Where-Object { $_ } | ForEach-Object { [char]$_ })
+                    Year         = $_.YearOfManufacture
+                    Native       = if ($native) { "$($native.HorizontalActivePixels)x$($native.VerticalActivePixels)" } else { "Unknown" }
+                    Instance     = $instance
+                }
+            } | Format-List | Out-File $outFile
+            # Active display paths: current mode, refresh rate, connection type and HDR (advanced color) state
+            Add-Type -TypeDefinition @"
+using System;
+using System.Runtime.InteropServices;
+public static class WFDisplayConfig {
+    [StructLayout(LayoutKind.Sequential)] public struct LUID { public uint LowPart; public int HighPart; }
+    [StructLayout(LayoutKind.Sequential)] public struct RATIONAL { public uint Numerator; public uint Denominator; }
+    [StructLayout(LayoutKind.Sequential)] public struct SOURCE_INFO { public LUID adapterId; public uint id; public uint modeInfoIdx; public uint statusFlags; }
+    [StructLayout(LayoutKind.Sequential)] public struct TARGET_INFO { public LUID adapterId; public uint id; public uint modeInfoIdx; public uint outputTechnology; public uint rotation; public uint scaling; public RATIONAL refreshRate; public uint scanLineOrdering; public int targetAvailable; public uint statusFlags; }
+    [StructLayout(LayoutKind.Sequential)] public struct PATH_INFO { public SOURCE_INFO sourceInfo; public TARGET_INFO targetInfo; public uint flags; }
+    [StructLayout(LayoutKind.Sequential)] public struct MODE_INFO { public uint infoType; public uint id; public LUID adapterId; [MarshalAs(UnmanagedType.ByValArray, SizeConst = 48)] public byte[] mode; }
+    [StructLayout(LayoutKind.Sequential)] public struct HEADER { public uint type; public uint size; public LUID adapterId; public uint id; }
+    [StructLayout(LayoutKind.Sequential, CharSet = CharSet.Unicode)] public struct TARGET_NAME { public HEADER header; public uint flags; public uint outputTechnology; public ushort edidManufactureId; public ushort edidProductCodeId; public uint connectorInstance; [MarshalAs(UnmanagedType.ByValTStr, SizeConst = 64)] public string monitorFriendlyDeviceName; [MarshalAs(UnmanagedType.ByValTStr, SizeConst = 128)] public string monitorDevicePath; }
+    [StructLayout(LayoutKind.Sequential)] public struct ADVANCED_COLOR { public HEADER header; public uint value; public uint colorEncoding; public uint bitsPerColorChannel; }
+    [DllImport("user32.dll")] public static extern int GetDisplayConfigBufferSizes(uint flags, out uint numPaths, out uint numModes);
+    [DllImport("user32.dll")] public static extern int QueryDisplayConfig(uint flags, ref uint numPaths, [Out] PATH_INFO[] paths, ref uint numModes, [Out] MODE_INFO[] modes, IntPtr topologyId);
+    [DllImport("user32.dll")] public static extern int DisplayConfigGetDeviceInfo(ref TARGET_NAME request);
+    [DllImport("user32.dll")] public static extern int DisplayConfigGetDeviceInfo(ref ADVANCED_COLOR request);
+}
+"@
+            $numPaths = 0; $numModes = 0
+            [void][WFDisplayConfig]::GetDisplayConfigBufferSizes(2, [ref]$numPaths, [ref]$numModes) # QDC_ONLY_ACTIVE_PATHS
+            $paths = New-Object WFDisplayConfig+PATH_INFO[] $numPaths
+            $modes = New-Object WFDisplayConfig+MODE_INFO[] $numModes
+            if ([WFDisplayConfig]::QueryDisplayConfig(2, [ref]$numPaths, $paths, [ref]$numModes, $modes, [IntPtr]::Zero) -eq 0) {
+                $paths | Select-Object -First $numPaths | ForEach-Object {
+                    $target = $_.targetInfo
+                    $name = New-Object WFDisplayConfig+TARGET_NAME
+                    $name.header.type = 2 # DISPLAYCONFIG_DEVICE_INFO_GET_TARGET_NAME
+                    $name.header.size = [System.Runtime.InteropServices.Marshal]::SizeOf($name)
+                    $name.header.adapterId = $target.adapterId
+                    $name.header.id = $target.id
+                    [void][WFDisplayConfig]::DisplayConfigGetDeviceInfo([ref]$name)
+                    $color = New-Object WFDisplayConfig+ADVANCED_COLOR
+                    $color.header.type = 9 # DISPLAYCONFIG_DEVICE_INFO_GET_ADVANCED_COLOR_INFO
+                    $color.header.size = [System.Runtime.InteropServices.Marshal]::SizeOf($color)
+                    $color.header.adapterId = $target.adapterId
+                    $color.header.id = $target.id
+                    $hdr = if ([WFDisplayConfig]::DisplayConfigGetDeviceInfo([ref]$color) -ne 0) { "Unknown" }
+                        elseif ($color.value -band 2) { "On" } elseif ($color.value -band 1) { "Supported, off" } else { "Not supported" }
+                    $sourceMode = $modes[$_.sourceInfo.modeInfoIdx].mode
+                    [PSCustomObject]@{
+                        Monitor        = $name.monitorFriendlyDeviceName
+                        Current        = "$([BitConverter]::ToUInt32($sourceMode, 0))x$([BitConverter]::ToUInt32($sourceMode, 4))"
+                        RefreshRate    = if ($target.refreshRate.Denominator) { "{0:N2} Hz" -f ($target.refreshRate.Numerator / $target.refreshRate.Denominator) } else { "Unknown" }
+                        Connection     = switch ($target.outputTechnology) { 0 { "VGA" } 4 { "DVI" } 5 { "HDMI" } 6 { "LVDS (internal)" } 10 { "DisplayPort" } 11 { "eDP (internal)" } 15 { "Miracast" } 16 { "Indirect (wired)" } 17 { "Indirect (virtual)" } 2147483648 { "Internal" } default { "Other ($_)" } }
+                        HDR            = $hdr
+                        BitsPerChannel = $color.bitsPerColorChannel
+                    }
+                } | Format-List | Out-File $outFile -Append
+            }
+            Get-CimInstance -ClassName Win32_VideoController | Select-Object Name, DriverVersion, CurrentHorizontalResolution, CurrentVerticalResolution, CurrentRefreshRate | Format-List | Out-File $outFile -Append }}
     )
-    # Add admin tasks if running with admin privileges
-if ($isAdmin) {
-    $diagnosticTasks += @(
+    # Admin tasks run only with admin privileges; skipped tasks are recorded in collection-info.json
+$skippedTasks = @()
+$adminTasks = @(
         @{ Name = "Read-Only Chkdsk"; Task = { param($filePath, $zipFilePath) Repair-Volume -DriveLetter C -Scan -Verbose | Out-File "$filePath\WindowsForum-Chkdsk.txt" }}
-        @{ Name = "DISM CheckHealth"; Task = { param($filePath, $zipFilePath) Start-Process dism -ArgumentList "/online /cleanup-image /checkhealth > $filePath\WindowsForum-DISMCheckHealth.txt" }}
+        @{ Name = "DISM CheckHealth"; Task = { param($filePath, $zipFilePath)
+            # Relay DISM's own percentage as job progress so the progress window keeps moving. DISM redraws its
+            # progress bar with carriage returns and only ends the line at 100%, so the output is read character
+            # by character and every "\r" segment is checked; the report keeps the last segment of each line.
+            # Only DISM is parsed: Repair-Volume prints no percentages, and the tool does not run sfc.
+            $dism = New-Object System.Diagnostics.Process
+            $dism.StartInfo.FileName = "$env:SystemRoot\System32\dism.exe"
+            $dism.StartInfo.Arguments = "/online /cleanup-image /checkhealth"
+            $dism.StartInfo.UseShellExecute = $false
+            $dism.StartInfo.RedirectStandardOutput = $true
+            $dism.StartInfo.CreateNoWindow = $true
+            [void]$dism.Start()
+            $report = New-Object System.Text.StringBuilder
+            $segment = New-Object System.Text.StringBuilder
+            $returned = $false
+            while (($character = $dism.StandardOutput.Read()) -ge 0) {
+                if ($character -eq 13 -or $character -eq 10) {
+                    if ($segment.ToString() -match '(\d+(\.\d+)?)%') { Write-Progress -Activity "DISM CheckHealth" -PercentComplete ([math]::Min(100, [int][double]$Matches[1])) }
+                    $returned = $character -eq 13
+                    if (!$returned) { [void]$report.AppendLine($segment.ToString()); [void]$segment.Clear() }
+                } else {
+                    # Text after a carriage return overwrites the segment before it, as it does on screen
+                    if ($returned) { [void]$segment.Clear(); $returned = $false }
+                    [void]$segment.Append([char]$character)
+                }
+            }
+            [void]$report.Append($segment.ToString())
+            $dism.WaitForExit()
+            $report.ToString() | Out-File "$filePath\WindowsForum-DISMCheckHealth.txt" }}
         #        @{ Name = "MSINFO32"; Task = { param($filePath, $zipFilePath) Start-Process msinfo32 -ArgumentList "/nfo $filePath\WindowsForum-MSInfo32.nfo" -Wait }},
         @{ Name = "Battery Report"; Task = { param($filePath, $zipFilePath) Start-Process powercfg -ArgumentList "/batteryreport /output `"$filePath\WindowsForum-BatteryReport.html`"" -NoNewWindow -Wait }},
 #        @{ Name = "Windows Update Log"; Task = { param($filePath, $zipFilePath) Get-WindowsUpdateLog | Out-File "$filePath\WindowsForum-WindowsUpdateLog.txt" }},
         @{ Name = "Driver Verifier"; Task = { param($filePath, $zipFilePath) verifier /querysettings | Out-File "$filePath\WindowsForum-DriverVerifierSettings.txt" }},
+        @{ Name = "Defrag/TRIM"; Task = { param($filePath, $zipFilePath)
+            $outFile = "$filePath\WindowsForum-DefragTrim.txt"
+            # DisableDeleteNotify = 0 means TRIM is enabled
+            $trimStatus = fsutil behavior query DisableDeleteNotify
+            $trimStatus | Out-File $outFile
+            if ($trimStatus -match 'NTFS DisableDeleteNotify = 1') {
+                Add-TaskFinding "Warning" "TRIM is disabled for NTFS volumes." "Enable it with 'fsutil behavior set DisableDeleteNotify 0' if this PC has an SSD." "WindowsForum-DefragTrim.txt"
+            }
+            Get-PhysicalDisk | Select-Object FriendlyName, MediaType, BusType, Size | Out-File $outFile -Append
+            Get-Volume | Where-Object { $_.DriveLetter -and $_.DriveType -eq 'Fixed' } | ForEach-Object {
+                "===== Volume $($_.DriveLetter): =====" | Out-File $outFile -Append
+                defrag "$($_.DriveLetter):" /a /v | Out-File $outFile -Append
+            } }},
         @{ Name = "BSOD Minidump"; Task = { param($filePath, $zipFilePath) $minidumpPath = "C:\Windows\Minidump\*"
             $destination = Join-Path -Path $filePath -ChildPath "Minidump"
             New-Item -ItemType Directory -Path $filePath\Minidump -Force
@@ -84,8 +1217,1211 @@ if ($isAdmin) {
                     Copy-Item -Path $_.FullName -Destination $destination -Force
                 }
                     }
-                }}
-            )
+                }},
+        @{ Name = "Boot Performance"; Task = { param($filePath, $zipFilePath)
+            $outFile = "$filePath\WindowsForum-BootPerformance.txt"
+            $events = Get-WinEvent -FilterHashtable @{ LogName = 'Microsoft-Windows-Diagnostics-Performance/Operational'; Id = 100..110 } -ErrorAction SilentlyContinue
+            $eventData = { param($record) $data = @{}; ([xml]$record.ToXml()).Event.EventData.Data | ForEach-Object { $data[$_.Name] = $_.'#text' }; $data }
+            # Event 100: one per boot, BootTime in milliseconds
+            "Boot duration trend (most recent first):" | Out-File $outFile
+            $events | Where-Object { $_.Id -eq 100 } | Select-Object -First 30 | ForEach-Object {
+                $data = & $eventData $_
+                [PSCustomObject]@{ Boot = $_.TimeCreated; BootSeconds = [math]::Round([int]$data.BootTime / 1000, 1); MainPathSeconds = [math]::Round([int]$data.MainPathBootTime / 1000, 1) }
+            } | Format-Table -AutoSize | Out-File $outFile -Append
+            # Events 101-110: apps, drivers, services and devices that slowed boot down
+            "Top boot degradation culprits:" | Out-File $outFile -Append
+            $events | Where-Object { $_.Id -ge 101 } | ForEach-Object {
+                $data = & $eventData $_
+                [PSCustomObject]@{ Culprit = if ($data.FriendlyName) { $data.FriendlyName } else { $data.Name }; File = $data.Name; DegradationMs = [int]$data.DegradationTime }
+            } | Group-Object File | ForEach-Object {
+                [PSCustomObject]@{
+                    Culprit           = $_.Group[0].Culprit
+                    File              = $_.Name
+                    Occurrences       = $_.Count
+                    TotalDelaySeconds = [math]::Round(($_.Group | Measure-Object DegradationMs -Sum).Sum / 1000, 1)
+                }
+            } | Sort-Object TotalDelaySeconds -Descending | Select-Object -First 15 | Format-Table -AutoSize | Out-File $outFile -Append }},
+        @{ Name = "Minidump Summary"; Task = { param($filePath, $zipFilePath)
+            $outFile = "$filePath\WindowsForum-MinidumpSummary.txt"
+            $bugcheckNames = @{
+                0x0A = "IRQL_NOT_LESS_OR_EQUAL"; 0x19 = "BAD_POOL_HEADER"; 0x1A = "MEMORY_MANAGEMENT"; 0x1E = "KMODE_EXCEPTION_NOT_HANDLED"
+                0x24 = "NTFS_FILE_SYSTEM"; 0x3B = "SYSTEM_SERVICE_EXCEPTION"; 0x4E = "PFN_LIST_CORRUPT"; 0x50 = "PAGE_FAULT_IN_NONPAGED_AREA"
+                0x7A = "KERNEL_DATA_INPAGE_ERROR"; 0x7B = "INACCESSIBLE_BOOT_DEVICE"; 0x7E = "SYSTEM_THREAD_EXCEPTION_NOT_HANDLED"; 0x7F = "UNEXPECTED_KERNEL_MODE_TRAP"
+                0x9F = "DRIVER_POWER_STATE_FAILURE"; 0xA0 = "INTERNAL_POWER_ERROR"; 0xBE = "ATTEMPTED_WRITE_TO_READONLY_MEMORY"; 0xC2 = "BAD_POOL_CALLER"
+                0xC4 = "DRIVER_VERIFIER_DETECTED_VIOLATION"; 0xC5 = "DRIVER_CORRUPTED_EXPOOL"; 0xD1 = "DRIVER_IRQL_NOT_LESS_OR_EQUAL"; 0xEF = "CRITICAL_PROCESS_DIED"
+                0xF4 = "CRITICAL_OBJECT_TERMINATION"; 0xFC = "ATTEMPTED_EXECUTE_OF_NOEXECUTE_MEMORY"; 0x101 = "CLOCK_WATCHDOG_TIMEOUT"; 0x109 = "CRITICAL_STRUCTURE_CORRUPTION"
+                0x116 = "VIDEO_TDR_FAILURE"; 0x117 = "VIDEO_TDR_TIMEOUT_DETECTED"; 0x119 = "VIDEO_SCHEDULER_INTERNAL_ERROR"; 0x124 = "WHEA_UNCORRECTABLE_ERROR"
+                0x133 = "DPC_WATCHDOG_VIOLATION"; 0x139 = "KERNEL_SECURITY_CHECK_FAILURE"; 0x13A = "KERNEL_MODE_HEAP_CORRUPTION"; 0x154 = "UNEXPECTED_STORE_EXCEPTION"
+                0x1000007E = "SYSTEM_THREAD_EXCEPTION_NOT_HANDLED_M"; 0x1000008E = "KERNEL_MODE_EXCEPTION_NOT_HANDLED_M"
+            }
+            # Kernel minidumps (DumpType 4) carry a triage block right after the header (0x2000 on x64/ARM64, 0x1000 on x86)
+            # with the loaded driver list, a string pool holding the driver names and a copy of the crashing thread's stack.
+            # All offsets count from the start of the file and are checked against its size, since dumps written during a
+            # crash are often truncated. Addresses are kept as [decimal] so 64-bit kernel addresses compare exactly.
+            $readModules = {
+                param([byte[]]$Bytes, [bool]$Is64)
+                $triage = if ($Is64) { 0x2000 } else { 0x1000 }
+                $u32 = { param($offset) if ($offset -ge 0 -and $offset + 4 -le $Bytes.Length) { [BitConverter]::ToUInt32($Bytes, $offset) } else { 0 } }
+                $pointer = { param($offset) if (!$Is64) { [decimal](& $u32 $offset) } elseif ($offset + 8 -le $Bytes.Length) { [decimal][BitConverter]::ToUInt64($Bytes, $offset) } else { [decimal]0 } }
+                $driverListOffset = & $u32 ($triage + 0x30); $driverCount = & $u32 ($triage + 0x34)
+                $poolOffset = & $u32 ($triage + 0x38); $poolSize = & $u32 ($triage + 0x3C)
+                $stackOffset = & $u32 ($triage + 0x28); $stackSize = & $u32 ($triage + 0x2C)
+                if ($driverCount -eq 0 -or $driverCount -gt 4096 -or $poolOffset + $poolSize -gt $Bytes.Length) { return }
+                # DUMP_STRING: a character count followed by UTF-16 text
+                $readName = {
+                    param($offset)
+                    if ($offset -lt $poolOffset -or $offset + 4 -gt $poolOffset + $poolSize) { return }
+                    $length = [BitConverter]::ToUInt32($Bytes, $offset)
+                    if ($length -eq 0 -or $length -gt 260 -or $offset + 4 + 2 * $length -gt $Bytes.Length) { return }
+                    $name = [System.Text.Encoding]::Unicode.GetString($Bytes, $offset + 4, 2 * $length)
+                    if ($name -match '^[\w.~ -]+\.(sys|dll|exe)$') { $name }
+                }
+                # DUMP_DRIVER_ENTRY is the name offset followed by the KLDR_DATA_TABLE_ENTRY, whose size changes between
+                # Windows versions: use the smallest entry size under which every entry names a driver
+                $ldrOffset = if ($Is64) { 8 } else { 4 }
+                $stride = $null
+                foreach ($candidate in 0x40..0x200) {
+                    if ($candidate % $ldrOffset -or $driverListOffset + $candidate * $driverCount -gt $Bytes.Length) { continue }
+                    $valid = $true
+                    for ($i = 0; $i -lt $driverCount -and $valid; $i++) { $valid = [bool](& $readName (& $u32 ($driverListOffset + $candidate * $i))) }
+                    if ($valid) { $stride = $candidate; break }
+                }
+                if (!$stride) { return }
+                $modules = @(for ($i = 0; $i -lt $driverCount; $i++) {
+                    $entry = $driverListOffset + $stride * $i + $ldrOffset
+                    $base = & $pointer ($entry + $(if ($Is64) { 0x30 } else { 0x18 }))
+                    [PSCustomObject]@{
+                        Name = & $readName (& $u32 ($driverListOffset + $stride * $i))
+                        Base = $base
+                        End  = $base + (& $u32 ($entry + $(if ($Is64) { 0x40 } else { 0x20 })))
+                    }
+                })
+                $stackAddresses = @(if ($stackSize -le 64KB -and $stackOffset + $stackSize -le $Bytes.Length) {
+                    for ($offset = $stackOffset; $offset -lt $stackOffset + $stackSize; $offset += $ldrOffset) { & $pointer $offset }
+                })
+                [PSCustomObject]@{ Modules = $modules; StackAddresses = $stackAddresses }
+            }
+            $kernelModules = "ntoskrnl.exe", "ntkrnlmp.exe", "ntkrnlpa.exe", "ntkrpamp.exe", "hal.dll", "halmacpi.dll"
+            $moduleVersions = @{}
+            # Driver versions come from the files on this machine; a bundle under -Analyze was collected elsewhere
+            $getModuleVersion = {
+                param($name)
+                if ($taskOptions.MinidumpPath) { return }
+                if (!$moduleVersions.ContainsKey($name)) {
+                    $file = "$env:SystemRoot\System32\drivers\$name", "$env:SystemRoot\System32\$name" | Where-Object { Test-Path -Path $_ } | Select-Object -First 1
+                    $moduleVersions[$name] = if ($file) { (Get-Item -Path $file).VersionInfo.FileVersion }
+                }
+                $moduleVersions[$name]
+            }
+            # Bugcheck code and parameters from the kernel dump header ("PAGEDU64" on x64/ARM64, "PAGEDUMP" on x86)
+            # -Analyze points the task at the dumps and exported System log of a bundle instead of this machine
+            $minidumpFolder = if ($taskOptions.MinidumpPath) { $taskOptions.MinidumpPath } else { "C:\Windows\Minidump" }
+            $dumps = Get-ChildItem -Path $minidumpFolder -Filter "*.dmp" -ErrorAction SilentlyContinue | Sort-Object LastWriteTime -Descending | ForEach-Object {
+                # Full memory dumps can be gigabytes; only minidumps are read whole
+                $bytes = if ($_.Length -le 16MB) { [System.IO.File]::ReadAllBytes($_.FullName) } else {
+                    $header = New-Object byte[] 0x60
+                    $stream = [System.IO.File]::OpenRead($_.FullName)
+                    [void]$stream.Read($header, 0, $header.Length)
+                    $stream.Dispose()
+                    $header
+                }
+                if ($bytes.Length -lt 0x60) { return }
+                $signature = [System.Text.Encoding]::ASCII.GetString($bytes, 0, 8)
+                if ($signature -eq "PAGEDU64") {
+                    $code = [BitConverter]::ToUInt32($bytes, 0x38)
+                    $parameterValues = 0..3 | ForEach-Object { [BitConverter]::ToUInt64($bytes, 0x40 + 8 * $_) }
+                    $parameters = $parameterValues | ForEach-Object { "0x{0:X16}" -f $_ }
+                } elseif ($signature -eq "PAGEDUMP") {
+                    $code = [BitConverter]::ToUInt32($bytes, 0x28)
+                    $parameterValues = 0..3 | ForEach-Object { [BitConverter]::ToUInt32($bytes, 0x2C + 4 * $_) }
+                    $parameters = $parameterValues | ForEach-Object { "0x{0:X8}" -f $_ }
+                } else { return }
+                $name = ($bugcheckNames.GetEnumerator() | Where-Object { $_.Key -eq $code }).Value
+                # Probable cause: the first driver, other than the kernel and HAL, that a bugcheck parameter or the
+                # crashing thread's stack points into; the kernel itself when nothing else is involved
+                $triage = & $readModules $bytes ($signature -eq "PAGEDU64")
+                $hits = @(foreach ($address in @($parameterValues | ForEach-Object { [decimal]$_ }) + $triage.StackAddresses) {
+                    $triage.Modules | Where-Object { $address -ge $_.Base -and $address -lt $_.End } | Select-Object -First 1 -ExpandProperty Name
+                })
+                $probable = @($hits | Where-Object { $kernelModules -notcontains $_ }) + $hits | Select-Object -First 1
+                [PSCustomObject]@{
+                    File          = $_.Name
+                    Time          = $_.LastWriteTime
+                    Code          = $code
+                    Bugcheck      = "0x{0:X8}" -f $code
+                    Name          = if ($name) { $name } else { "Unknown" }
+                    Parameters    = $parameters -join ", "
+                    ProbableCause = if ($probable) { "$probable $(& $getModuleVersion $probable)".Trim() } elseif ($triage) { "not determined" } else { "no driver list in this dump" }
+                    Driver        = $probable
+                    Modules       = @($triage.Modules | ForEach-Object {
+                        [PSCustomObject]@{ Name = $_.Name; Base = "0x{0:X}" -f [uint64]$_.Base; Size = [uint64]($_.End - $_.Base); Version = & $getModuleVersion $_.Name }
+                    })
+                }
+            }
+            if ($dumps) {
+                $dumps | Select-Object * -ExcludeProperty Code, Driver, Modules | Format-List | Out-File $outFile
+                # The loaded drivers of each dump, for checking versions against the probable cause
+                $dumps | ForEach-Object {
+                    "===== $($_.File) ($($_.Modules.Count) loaded modules) ====="
+                    $_.Modules | Format-Table Name, Base, Size, Version -AutoSize | Out-String -Width 200
+                } | Out-File "$filePath\WindowsForum-MinidumpModules.txt"
+            }
+            else { "No readable minidumps found in $minidumpFolder." | Out-File $outFile }
+            # Correlate bugchecks across dumps: one recurring area is more telling than any single crash
+            if ($dumps) {
+                $dumps = @($dumps)
+                $bugcheckAreas = @{
+                    "memory"   = 0x19, 0x1A, 0x4E, 0x50, 0x13A
+                    "storage"  = 0x24, 0x7A, 0x7B, 0xF4, 0x154
+                    "graphics" = 0x116, 0x117, 0x119
+                    "hardware" = 0x7F, 0x101, 0x124
+                    "driver"   = 0x0A, 0x1E, 0x3B, 0x7E, 0x9F, 0xBE, 0xC2, 0xC4, 0xC5, 0xD1, 0xFC, 0x133, 0x1000007E, 0x1000008E
+                }
+                $areas = $dumps | ForEach-Object {
+                    $code = $_.Code
+                    $area = $bugcheckAreas.Keys | Where-Object { $bugcheckAreas[$_] -contains $code } | Select-Object -First 1
+                    if ($area) { $area } else { "other" }
+                }
+                $topArea = $areas | Group-Object | Sort-Object Count -Descending | Select-Object -First 1
+                $distinctCodes = @($dumps.Code | Select-Object -Unique).Count
+                $confidence = if ($dumps.Count -ge 3 -and $topArea.Count -eq $dumps.Count) { "high" } elseif ($dumps.Count -ge 2 -and $topArea.Count / $dumps.Count -ge 0.6) { "medium" } else { "low" }
+                $areaAdvice = @{
+                    "memory"   = "Test the RAM with Windows Memory Diagnostic or MemTest86 and undo any memory overclock (XMP/EXPO)."
+                    "storage"  = "Check the disk's health and cables, and update the storage controller driver and SSD firmware."
+                    "graphics" = "Reinstall the graphics driver with a clean install, or try an older version."
+                    "hardware" = "Check temperatures and the power supply, and undo any CPU overclock."
+                }
+                $recommendation = $areaAdvice[$topArea.Name]
+                # The same probable driver in most dumps outweighs the bugcheck areas
+                $topDriver = $dumps | Where-Object { $_.Driver -and $kernelModules -notcontains $_.Driver } | Group-Object Driver | Sort-Object Count -Descending | Select-Object -First 1
+                $pattern = if ($dumps.Count -ge 2 -and $topDriver -and $topDriver.Count / $dumps.Count -ge 0.6) {
+                    $confidence = if ($dumps.Count -ge 3 -and $topDriver.Count -eq $dumps.Count) { "high" } else { "medium" }
+                    $recommendation = "Update, roll back or uninstall the software that installed $($topDriver.Name)."
+                    "$($topDriver.Count) of $($dumps.Count) dumps name $($topDriver.Name) as the probable cause, pointing at a single driver ($confidence confidence)."
+                } elseif (@($areas | Select-Object -Unique).Count -ge 3) {
+                    $confidence = "medium"
+                    $recommendation = $areaAdvice["hardware"]
+                    "$($dumps.Count) dumps with $distinctCodes different bugchecks across $(@($areas | Select-Object -Unique).Count) areas; varied crashes usually point at hardware (RAM, PSU, overclocking) rather than one driver ($confidence confidence)."
+                } elseif ($distinctCodes -eq 1 -and $dumps.Count -ge 2) {
+                    "All $($dumps.Count) dumps are $($dumps[0].Name), pointing at a single recurring $($topArea.Name) cause ($confidence confidence)."
+                } else {
+                    "$($topArea.Count) of $($dumps.Count) dumps point at $($topArea.Name) ($confidence confidence)."
+                }
+                "" | Out-File $outFile -Append
+                "Pattern: $pattern" | Out-File $outFile -Append
+                # One crash, or crashes that don't agree, are worth a look but not yet a diagnosis
+                $severity = if ($dumps.Count -ge 2 -and $confidence -ne "low") { "Critical" } else { "Warning" }
+                Add-TaskFinding $severity "BSODs: $pattern" "$(if ($recommendation) { "$recommendation " })Post WindowsForum-MinidumpSummary.txt and the Minidump folder in your forum thread for analysis." "WindowsForum-MinidumpSummary.txt"
+            }
+            # Crashes recorded in the System log, including ones whose dumps were deleted or never written
+            "===== BugCheck events (System log) =====" | Out-File $outFile -Append
+            $bugcheckFilter = @{ LogName = 'System'; ProviderName = 'Microsoft-Windows-WER-SystemErrorReporting'; Id = 1001 }
+            if ($taskOptions.Analyze) { $bugcheckFilter.Remove('LogName'); $bugcheckFilter.Path = @($taskOptions.EventLogPaths) }
+            if ($taskOptions.Analyze -and !$taskOptions.EventLogPaths) { "No event logs in this bundle." | Out-File $outFile -Append }
+            else {
+                Get-WinEvent -FilterHashtable $bugcheckFilter -MaxEvents 50 -ErrorAction SilentlyContinue |
+                    Select-Object TimeCreated, Message | Format-List | Out-File $outFile -Append
+            } }}
+    )
+if ($isAdmin) {
+    $diagnosticTasks += $adminTasks
+} else {
+    $skippedTasks += $adminTasks | ForEach-Object { [PSCustomObject]@{ Task = $_.Name; Reason = "Needs administrator rights" } }
+}
+# Add emulation-specific tasks on Windows on ARM (Win32_Processor Architecture 12 = ARM64)
+$processorArchitecture = (Get-CimInstance -ClassName Win32_Processor).Architecture
+$arm64Tasks = @(
+        @{ Name = "ARM64 Emulation"; Task = { param($filePath, $zipFilePath)
+            $outFile = "$filePath\WindowsForum-Arm64Emulation.txt"
+            Add-Type -TypeDefinition @"
+using System;
+using System.Runtime.InteropServices;
+public static class WFProcessMachine {
+    [StructLayout(LayoutKind.Sequential)] public struct PROCESS_MACHINE_INFORMATION { public ushort ProcessMachine; public ushort Res0; public uint MachineAttributes; }
+    [DllImport("kernel32.dll", SetLastError = true)] public static extern IntPtr OpenProcess(uint access, bool inherit, int pid);
+    [DllImport("kernel32.dll")] public static extern bool CloseHandle(IntPtr handle);
+    [DllImport("kernel32.dll", SetLastError = true)] public static extern bool GetProcessInformation(IntPtr process, int infoClass, out PROCESS_MACHINE_INFORMATION info, int size);
+    public static int GetMachine(int pid) {
+        IntPtr handle = OpenProcess(0x1000, false, pid); // PROCESS_QUERY_LIMITED_INFORMATION
+        if (handle == IntPtr.Zero) return -1;
+        try {
+            PROCESS_MACHINE_INFORMATION info;
+            return GetProcessInformation(handle, 9, out info, Marshal.SizeOf(typeof(PROCESS_MACHINE_INFORMATION))) ? info.ProcessMachine : -1; // ProcessMachineTypeInfo
+        } finally { CloseHandle(handle); }
+    }
+}
+"@
+            $machineNames = @{ 0xAA64 = "ARM64 (native)"; 0x8664 = "x64 (emulated)"; 0x14C = "x86 (emulated)"; 0x1C4 = "ARM32"; -1 = "Unknown (access denied)" }
+            "Per-process architecture:" | Out-File $outFile
+            Get-Process | Sort-Object ProcessName | ForEach-Object {
+                $machine = [WFProcessMachine]::GetMachine($_.Id)
+                [PSCustomObject]@{ Process = $_.ProcessName; Id = $_.Id; Architecture = $machineNames[$machine] }
+            } | Format-Table -AutoSize | Out-File $outFile -Append
+            # Prism/emulation settings: per-app compatibility layers and the Wow64 emulator configuration
+            "Per-app emulation settings (AppCompatFlags\Layers):" | Out-File $outFile -Append
+            foreach ($hive in "HKCU", "HKLM") {
+                Get-ItemProperty "$($hive):\Software\Microsoft\Windows NT\CurrentVersion\AppCompatFlags\Layers" -ErrorAction SilentlyContinue |
+                    Select-Object * -ExcludeProperty PS* | Format-List | Out-File $outFile -Append
+            }
+            "Wow64 emulator configuration:" | Out-File $outFile -Append
+            Get-ChildItem "HKLM:\SOFTWARE\Microsoft\Wow64" -Recurse -ErrorAction SilentlyContinue | ForEach-Object {
+                $key = $_
+                $key.Name
+                $key.GetValueNames() | ForEach-Object { "    $_ = $($key.GetValue($_))" }
+            } | Out-File $outFile -Append
+            # Loaded drivers that are not ARM64 binaries cannot run natively on this machine
+            "Running drivers by image machine type:" | Out-File $outFile -Append
+            $drivers = Get-CimInstance -ClassName Win32_SystemDriver -Filter "State = 'Running'" | ForEach-Object {
+                $path = $_.PathName -replace '^\\\?\?\\', '' -replace '^\\SystemRoot', $env:windir -replace '^(?i)system32', "$env:windir\System32"
+                $machine = try {
+                    $bytes = New-Object byte[] 4096
+                    $stream = [System.IO.File]::OpenRead($path)
+                    [void]$stream.Read($bytes, 0, $bytes.Length)
+                    $stream.Dispose()
+                    $peOffset = [BitConverter]::ToInt32($bytes, 0x3C)
+                    switch ([BitConverter]::ToUInt16($bytes, $peOffset + 4)) { 0xAA64 { "ARM64" } 0x8664 { "x64" } 0x14C { "x86" } default { "Other (0x{0:X})" -f $_ } }
+                } catch { "Unreadable" }
+                [PSCustomObject]@{ Driver = $_.Name; Machine = $machine; Path = $path }
+            }
+            $drivers | Sort-Object Machine, Driver | Format-Table -AutoSize | Out-File $outFile -Append
+            foreach ($driver in $drivers | Where-Object { $_.Machine -notin 'ARM64', 'Unreadable' }) {
+                Add-TaskFinding "Warning" "Driver $($driver.Driver) is a $($driver.Machine) binary on an ARM64 machine." "Look for an ARM64 version of the software that installed $($driver.Path)." "WindowsForum-Arm64Emulation.txt"
+            } }}
+    )
+if ($processorArchitecture -contains 12) {
+    $diagnosticTasks += $arm64Tasks
+} else {
+    $skippedTasks += $arm64Tasks | ForEach-Object { [PSCustomObject]@{ Task = $_.Name; Reason = "Only runs on Windows on ARM" } }
+}
+
+# -MonitorMinutes: intermittent slowdowns do not show up in a single snapshot, so this task samples the
+# machine for a while. Formatted perf classes are used because counter paths are localized.
+if ($MonitorMinutes -gt 0) {
+    $diagnosticTasks += @(
+        @{ Name = "Performance Timeline"; Task = { param($filePath)
+            $logicalProcessors = (Get-CimInstance -ClassName Win32_ComputerSystem).NumberOfLogicalProcessors
+            $end = (Get-Date).AddMinutes($taskOptions.MonitorMinutes)
+            $start = Get-Date
+            $samples = New-Object System.Collections.ArrayList
+            while ((Get-Date) -lt $end) {
+                $cpu = Get-CimInstance -ClassName Win32_PerfFormattedData_PerfOS_Processor -Filter "Name = '_Total'"
+                $memory = Get-CimInstance -ClassName Win32_PerfFormattedData_PerfOS_Memory
+                $disk = Get-CimInstance -ClassName Win32_PerfFormattedData_PerfDisk_PhysicalDisk -Filter "Name = '_Total'"
+                $top = Get-CimInstance -ClassName Win32_PerfFormattedData_PerfProc_Process | Where-Object { $_.Name -notin '_Total', 'Idle' } |
+                    Sort-Object PercentProcessorTime -Descending | Select-Object -First 5
+                [void]$samples.Add([PSCustomObject]@{
+                    Time             = (Get-Date).ToString('o')
+                    CpuPercent       = [int]$cpu.PercentProcessorTime
+                    AvailableMB      = [int]$memory.AvailableMBytes
+                    CommittedPercent = [int]$memory.PercentCommittedBytesInUse
+                    DiskQueue        = [int]$disk.CurrentDiskQueueLength
+                    DiskBusyPercent  = [math]::Min(100, [int]$disk.PercentDiskTime)
+                    TopProcesses     = ($top | ForEach-Object { "$($_.Name) ($([math]::Round($_.PercentProcessorTime / $logicalProcessors))%)" }) -join '; '
+                })
+                Write-Progress -Activity "Performance Timeline" -PercentComplete ([math]::Min(100, 100 * ((Get-Date) - $start).TotalSeconds / ($end - $start).TotalSeconds))
+                Start-Sleep -Seconds $taskOptions.MonitorInterval
+            }
+            $samples | Export-Csv -Path "$filePath\WindowsForum-PerformanceTimeline.csv" -NoTypeInformation -Encoding UTF8
+            ConvertTo-Json -InputObject @($samples) | Out-File "$filePath\WindowsForum-PerformanceTimeline.json"
+            # Sustained pressure: more than a quarter of the samples over the threshold
+            $sustained = { param($property, $threshold) @($samples | Where-Object { $_.$property -ge $threshold }).Count -gt $samples.Count / 4 }
+            $busiest = ($samples.TopProcesses -split '; ' | ForEach-Object { ($_ -split ' \(')[0] } | Group-Object | Sort-Object Count -Descending | Select-Object -First 3).Name -join ', '
+            if (& $sustained "CpuPercent" 90) { Add-TaskFinding "Warning" "CPU usage was 90% or higher for long stretches of the $($taskOptions.MonitorMinutes)-minute timeline (busiest: $busiest)." "Check what the busiest processes are doing; see the TopProcesses column of the timeline." "WindowsForum-PerformanceTimeline.csv" }
+            if (& $sustained "CommittedPercent" 90) { Add-TaskFinding "Warning" "Committed memory stayed at 90% or more of the commit limit during the timeline." "Close memory-hungry programs, add RAM or enlarge the page file." "WindowsForum-PerformanceTimeline.csv" }
+            if (& $sustained "DiskQueue" 4) { Add-TaskFinding "Warning" "The disk queue stayed long (4 or more requests) during the timeline, so the disk was a bottleneck." "Check the drive's health and what is reading or writing (see TopProcesses)." "WindowsForum-PerformanceTimeline.csv" } }}
+    )
+}
+
+# Task categories for -Category; Snapshot feeds the findings and summary and always runs
+$taskCategories = @{
+    "Comp System" = "System"; "OS" = "System"; "BIOS" = "System"; "BaseBoard" = "System"; "Processor" = "System"; "Physical Mem" = "System"
+    "Dev Mem Addr" = "Hardware"; "DMA Channel" = "Hardware"; "IRQ Resource" = "Hardware"; "Sys Devices" = "Hardware"; "Printer" = "Hardware"
+    "Disk Drive" = "Storage"; "Disk Partition" = "Storage"; "Net Adapter" = "Network"
+    "Environment" = "Software"; "Startup Cmd" = "Software"; "Sys Driver" = "Drivers"
+    "DXDiag" = "Display"; "Displays" = "Display"
+    "SystemInfo" = "System"; "Time Zone/Locale" = "System"; "Performance Data" = "Performance"; "Processes" = "Performance"; "Boot Performance" = "Performance"
+    "Drivers" = "Drivers"; "Driver Blocklist" = "Drivers"; "Driver Verifier" = "Drivers"; "ARM64 Emulation" = "Drivers"
+    "Event Logs" = "Events"; "Event Log Summary" = "Events"; "Windows Update Log" = "Updates"; "DISM CheckHealth" = "Updates"
+    "IPConfig" = "Network"; "HOSTS File" = "Network"; "Dsregcmd" = "Network"
+    "Installed Programs" = "Software"; "Windows Store Apps" = "Software"; "System Services" = "Software"; "Scheduled Tasks" = "Software"
+    "AppLocker/WDAC" = "Security"
+    "Chkdsk History" = "Storage"; "Read-Only Chkdsk" = "Storage"; "Defrag/TRIM" = "Storage"
+    "USB Power" = "Power"; "Battery Report" = "Power"; "Performance Timeline" = "Performance"
+    "BSOD Minidump" = "Crashes"; "Minidump Summary" = "Crashes"
+}
+# What each task collects, for -TaskInfo
+$taskDescriptions = @{
+    "Comp System" = "WMI Win32_ComputerSystem: manufacturer, model, domain, memory."; "OS" = "WMI Win32_OperatingSystem: edition, build, install and boot time."
+    "BIOS" = "WMI Win32_BIOS: firmware vendor, version and release date."; "BaseBoard" = "WMI Win32_BaseBoard: motherboard maker and model."
+    "Processor" = "WMI Win32_Processor: CPU model, cores, clocks and architecture."; "Physical Mem" = "WMI Win32_PhysicalMemory: installed memory modules."
+    "Dev Mem Addr" = "WMI Win32_DeviceMemoryAddress: device memory ranges."; "DMA Channel" = "WMI Win32_DMAChannel: DMA channels in use."
+    "IRQ Resource" = "WMI Win32_IRQResource: interrupt assignments."; "Disk Drive" = "WMI Win32_DiskDrive: physical disks, models and interfaces."
+    "Disk Partition" = "WMI Win32_DiskPartition: partitions and their sizes."; "Sys Devices" = "WMI Win32_SystemDevices: system device associations."
+    "Net Adapter" = "WMI Win32_NetworkAdapter: network adapters and their state."; "Printer" = "WMI Win32_Printer: installed printers."
+    "Environment" = "WMI Win32_Environment: environment variables."; "Startup Cmd" = "WMI Win32_StartupCommand: programs started at logon."
+    "Sys Driver" = "WMI Win32_SystemDriver: kernel drivers and their state."
+    "DXDiag" = "DirectX diagnostics report plus a JSON copy; flags devices with problem notes."
+    "SystemInfo" = "systeminfo output plus a typed JSON copy (build, boot time, hotfixes)."
+    "Drivers" = "Signed PnP drivers with provider, version, date and signing status."
+    "Event Logs" = "Exports the System and Application event logs (.evtx)."
+    "Event Log Summary" = "Most frequent errors of the last 7 days, grouped by provider and event ID."
+    "IPConfig" = "ipconfig /all network configuration."; "Installed Programs" = "Installed programs and versions."
+    "Windows Store Apps" = "Store apps, broken packages and recent Appx deployment errors."
+    "System Services" = "All services with status and start type."; "Processes" = "Running processes."
+    "Performance Data" = "A Get-Counter sample of the default performance counters."; "HOSTS File" = "Copy of the HOSTS file."
+    "Dsregcmd" = "Azure AD / domain join state and the domain secure channel."; "Scheduled Tasks" = "All scheduled tasks."
+    "Windows Update Log" = "Windows Update client operational log as text."
+    "Snapshot" = "Small JSON snapshot used for findings, -Compare and baselines; always runs."
+    "AppLocker/WDAC" = "Effective AppLocker policy and whether a WDAC policy is active."
+    "Driver Blocklist" = "Installed drivers that appear on the bundled list of known BSOD causes."
+    "Chkdsk History" = "Results of earlier chkdsk/autochk runs from the Application log."
+    "USB Power" = "USB selective suspend and per-device USB power management settings."
+    "Time Zone/Locale" = "Time zone, locale, UI language, code pages and language packs."
+    "Displays" = "Monitors with native and current resolution, refresh rate, connection and HDR."
+    "Read-Only Chkdsk" = "Online read-only scan of drive C: (Repair-Volume -Scan)."
+    "DISM CheckHealth" = "DISM component store health check."; "MSINFO32" = "System Information report (disabled)."
+    "Battery Report" = "powercfg battery report."; "Driver Verifier" = "Driver Verifier settings."
+    "Defrag/TRIM" = "Defrag analysis of fixed volumes and the TRIM setting."
+    "BSOD Minidump" = "Copies the minidumps from C:\Windows\Minidump."
+    "Boot Performance" = "Boot duration trend and the apps, drivers and services that slowed boot."
+    "Minidump Summary" = "Bugcheck code, parameters, probable driver and loaded modules of each minidump, crash pattern and BugCheck events."
+    "ARM64 Emulation" = "Native vs emulated processes, emulation settings and driver machine types."
+    "Performance Timeline" = "CPU, memory, disk queue and top processes sampled over -MonitorMinutes."
+}
+if ($uiTranslation.TaskDescriptions) { $uiTranslation.TaskDescriptions.PSObject.Properties | ForEach-Object { $taskDescriptions[$_.Name] = $_.Value } }
+
+# Presets for -Preset: the tasks helpers usually ask for with each kind of problem
+$taskPresets = @{
+    "bsod"        = "Comp System", "OS", "BIOS", "Processor", "Physical Mem", "SystemInfo", "Drivers", "Sys Driver", "Driver Blocklist", "Driver Verifier",
+                    "Event Log Summary", "BSOD Minidump", "Minidump Summary", "Disk Drive", "Chkdsk History", "DXDiag"
+    "network"     = "Comp System", "OS", "Net Adapter", "IPConfig", "HOSTS File", "Dsregcmd", "Drivers", "Event Log Summary", "System Services"
+    "performance" = "Comp System", "OS", "Processor", "Physical Mem", "Processes", "Performance Data", "Startup Cmd", "Scheduled Tasks", "System Services",
+                    "Disk Drive", "Defrag/TRIM", "Event Log Summary", "USB Power"
+    # The basic hardware, Windows and health summaries, without the slow exports (DXDiag, SystemInfo, event logs)
+    "quick"       = "Comp System", "OS", "BIOS", "Processor", "Physical Mem", "Disk Drive", "Net Adapter", "Sys Driver", "Event Log Summary",
+                    "Minidump Summary"
+    "slow-boot"   = "Comp System", "OS", "Boot Performance", "Startup Cmd", "Scheduled Tasks", "System Services", "Sys Driver", "Disk Drive", "Defrag/TRIM",
+                    "Event Log Summary", "Windows Update Log"
+}
+# Names are checked against every task, so excluding an admin-only task as a standard user, or one a preset or
+# category already left out (as "Retry failed" does), is not an error
+$allTaskNames = @($diagnosticTasks + $adminTasks + $arm64Tasks | ForEach-Object { $_.Name } | Sort-Object -Unique)
+if ($Preset) {
+    # The Performance Timeline only exists when -MonitorMinutes asked for it, so no preset drops it
+    $presetTasks = @($taskPresets[$Preset]) + "Snapshot", "Performance Timeline"
+    $skippedTasks += $diagnosticTasks | Where-Object { $presetTasks -notcontains $_.Name } |
+        ForEach-Object { [PSCustomObject]@{ Task = $_.Name; Reason = "Not in -Preset $Preset" } }
+    $diagnosticTasks = @($diagnosticTasks | Where-Object { $presetTasks -contains $_.Name })
+}
+if ($Category) {
+    $unknown = $Category | Where-Object { $taskCategories.Values -notcontains $_ }
+    if ($unknown) { Write-Warning "Unknown categories: $($unknown -join ', '). Valid: $(($taskCategories.Values | Sort-Object -Unique) -join ', ')" }
+    $skippedTasks += $diagnosticTasks | Where-Object { $_.Name -ne "Snapshot" -and $Category -notcontains $taskCategories[$_.Name] } |
+        ForEach-Object { [PSCustomObject]@{ Task = $_.Name; Reason = "Not in -Category $($Category -join ',')" } }
+    $diagnosticTasks = @($diagnosticTasks | Where-Object { $_.Name -eq "Snapshot" -or $Category -contains $taskCategories[$_.Name] })
+}
+if ($Exclude) {
+    $unknown = $Exclude | Where-Object { $pattern = $_; !($allTaskNames | Where-Object { $_ -like $pattern }) }
+    if ($unknown) { Write-Error "-Exclude matches no task: $($unknown -join ', '). Tasks: $($allTaskNames -join ', ')"; exit 1 }
+    $isExcluded = { param($name) $name -ne "Snapshot" -and @($Exclude | Where-Object { $name -like $_ }).Count -gt 0 }
+    $skippedTasks += $diagnosticTasks | Where-Object { & $isExcluded $_.Name } | ForEach-Object { [PSCustomObject]@{ Task = $_.Name; Reason = "Excluded with -Exclude" } }
+    $diagnosticTasks = @($diagnosticTasks | Where-Object { !(& $isExcluded $_.Name) })
+}
+# -Interactive: choose from what is left after the filters above by number, range, category or name
+if ($Interactive -and ($DryRun -or !$offlineMode)) {
+    $selectable = @($diagnosticTasks | Where-Object { $_.Name -ne "Snapshot" })
+    for ($i = 0; $i -lt $selectable.Count; $i++) { "{0,3}. {1,-24} {2}" -f ($i + 1), $selectable[$i].Name, $taskCategories[$selectable[$i].Name] }
+    $answer = Read-Host "Tasks to run: numbers or ranges (1,4-7), categories or names, Enter for all"
+    if ($answer.Trim()) {
+        $chosenNames = @(foreach ($token in $answer -split '[,\s]+' | Where-Object { $_ }) {
+            if ($token -match '^(\d+)(?:-(\d+))?$') {
+                $last = if ($Matches[2]) { [int]$Matches[2] } else { [int]$Matches[1] }
+                [int]$Matches[1]..$last | Where-Object { $_ -ge 1 -and $_ -le $selectable.Count } | ForEach-Object { $selectable[$_ - 1].Name }
+            } else {
+                ($selectable | Where-Object { $taskCategories[$_.Name] -eq $token -or $_.Name -like $token }).Name
+            }
+        })
+        $skippedTasks += $selectable | Where-Object { $chosenNames -notcontains $_.Name } | ForEach-Object { [PSCustomObject]@{ Task = $_.Name; Reason = "Not selected" } }
+        $diagnosticTasks = @($diagnosticTasks | Where-Object { $_.Name -eq "Snapshot" -or $chosenNames -contains $_.Name })
+    }
+}
+# -SelectTasks: the same choice in a window. Tasks are grouped under their categories and all checked;
+# a category's box checks or clears all of its (shown) tasks, and the description shows as a tooltip.
+# The search box filters by name, description and category; $taskChecked keeps the choices of hidden tasks.
+if ($SelectTasks -and !$Quiet -and !$Console -and ($DryRun -or !$offlineMode)) {
+    $selectForm = New-Object System.Windows.Forms.Form
+    $selectForm.Text = "WindowsForum.com Diagnostic Tool $($version) - $(Get-WFText ChooseTasks)"
+    $selectForm.StartPosition = 'CenterScreen'
+    $selectForm.TopMost = $true
+    $selectForm.MinimizeBox = $false
+    $selectForm.Size = New-Object System.Drawing.Size(420, 560)
+
+    # Picking a preset checks exactly its tasks (those still in the list), like -Preset on the command line
+    $presetBox = New-Object System.Windows.Forms.ComboBox
+    $presetBox.Location = New-Object System.Drawing.Point(10, 10)
+    $presetBox.Size = New-Object System.Drawing.Size(130, 20)
+    $presetBox.DropDownStyle = [System.Windows.Forms.ComboBoxStyle]::DropDownList
+    [void]$presetBox.Items.Add((Get-WFText AllTasks))
+    $taskPresets.Keys | Sort-Object | ForEach-Object { [void]$presetBox.Items.Add($_) }
+    $selectForm.Controls.Add($presetBox)
+
+    $searchBox = New-Object System.Windows.Forms.TextBox
+    $searchBox.Location = New-Object System.Drawing.Point(150, 10)
+    $searchBox.Size = New-Object System.Drawing.Size(245, 20)
+    $searchBox.Anchor = "Top, Left, Right"
+    $selectForm.Controls.Add($searchBox)
+
+    $taskTree = New-Object System.Windows.Forms.TreeView
+    $taskTree.Location = New-Object System.Drawing.Point(10, 40)
+    $taskTree.Size = New-Object System.Drawing.Size(385, 430)
+    $taskTree.Anchor = "Top, Bottom, Left, Right"
+    $taskTree.CheckBoxes = $true
+    $taskTree.ShowNodeToolTips = $true
+    $selectableTasks = @($diagnosticTasks | Where-Object { $_.Name -ne "Snapshot" })
+    $taskChecked = @{}
+    $selectableTasks | ForEach-Object { $taskChecked[$_.Name] = $true }
+    $fillTaskTree = {
+        $search = "*$($searchBox.Text.Trim())*"
+        $taskTree.BeginUpdate()
+        $taskTree.Nodes.Clear()
+        $shown = $selectableTasks | Where-Object { $_.Name -like $search -or $taskDescriptions[$_.Name] -like $search -or $taskCategories[$_.Name] -like $search }
+        foreach ($group in $shown | Group-Object { $taskCategories[$_.Name] } | Sort-Object Name) {
+            $categoryNode = $taskTree.Nodes.Add($group.Name, "$($group.Name) ($($group.Count))")
+            foreach ($task in $group.Group) {
+                $taskNode = $categoryNode.Nodes.Add($task.Name, $task.Name)
+                $taskNode.Checked = $taskChecked[$task.Name]
+                $taskNode.ToolTipText = $taskDescriptions[$task.Name]
+            }
+            $categoryNode.Checked = @($categoryNode.Nodes | Where-Object { $_.Checked }).Count -gt 0
+        }
+        $taskTree.ExpandAll()
+        $taskTree.EndUpdate()
+    }
+    . $fillTaskTree
+    $searchBox.Add_TextChanged({ . $fillTaskTree })
+    $presetBox.Add_SelectedIndexChanged({
+        $presetTasks = @($taskPresets[$presetBox.SelectedItem]) + "Performance Timeline"
+        $selectableTasks | ForEach-Object { $taskChecked[$_.Name] = $presetBox.SelectedIndex -eq 0 -or $presetTasks -contains $_.Name }
+        . $fillTaskTree
+    })
+    $presetBox.SelectedIndex = if ($Preset) { $presetBox.Items.IndexOf($Preset) } else { 0 }
+    $taskTree.Add_AfterCheck({
+        param($sender, $e)
+        # Checks set from here arrive with Action Unknown and must not cascade back
+        if ($e.Action -eq [System.Windows.Forms.TreeViewAction]::Unknown) { return }
+        if ($e.Node.Level -eq 0) {
+            foreach ($child in $e.Node.Nodes) { $child.Checked = $e.Node.Checked; $taskChecked[$child.Name] = $e.Node.Checked }
+        } else {
+            $taskChecked[$e.Node.Name] = $e.Node.Checked
+            $e.Node.Parent.Checked = @($e.Node.Parent.Nodes | Where-Object { $_.Checked }).Count -gt 0
+        }
+    })
+    $selectForm.Controls.Add($taskTree)
+
+    $runButton = New-Object System.Windows.Forms.Button
+    $runButton.Text = Get-WFText Run
+    $runButton.Location = New-Object System.Drawing.Point(230, 480)
+    $runButton.Anchor = "Bottom, Right"
+    $runButton.DialogResult = [System.Windows.Forms.DialogResult]::OK
+    $selectForm.Controls.Add($runButton)
+    $selectForm.AcceptButton = $runButton
+    $cancelButton = New-Object System.Windows.Forms.Button
+    $cancelButton.Text = Get-WFText Cancel
+    $cancelButton.Location = New-Object System.Drawing.Point(315, 480)
+    $cancelButton.Anchor = "Bottom, Right"
+    $cancelButton.DialogResult = [System.Windows.Forms.DialogResult]::Cancel
+    $selectForm.Controls.Add($cancelButton)
+    $selectForm.CancelButton = $cancelButton
+
+    if ($selectForm.ShowDialog() -ne [System.Windows.Forms.DialogResult]::OK) { exit }
+    $chosenNames = @($taskChecked.Keys | Where-Object { $taskChecked[$_] })
+    $selectForm.Dispose()
+    $skippedTasks += $diagnosticTasks | Where-Object { $_.Name -ne "Snapshot" -and $chosenNames -notcontains $_.Name } | ForEach-Object { [PSCustomObject]@{ Task = $_.Name; Reason = "Not selected" } }
+    $diagnosticTasks = @($diagnosticTasks | Where-Object { $_.Name -eq "Snapshot" -or $chosenNames -contains $_.Name })
+}
+
+# -DryRun: what each task would run and touch, read from the task's own script (programs and cmdlets
+# from its syntax tree, WMI classes, registry keys, event logs and files from the text)
+function Get-WFTaskPlan {
+    param($Task)
+    $text = $Task.Task.ToString()
+    $commands = $Task.Task.Ast.FindAll({ $args[0] -is [System.Management.Automation.Language.CommandAst] }, $true)
+    $programs = @(); $cmdlets = @()
+    foreach ($command in $commands) {
+        $name = $command.GetCommandName()
+        if ($name -eq "Start-Process" -and $command.CommandElements.Count -gt 1) { $name = $command.CommandElements[1].Extent.Text.Trim('"', "'") }
+        if (!$name) { continue }
+        $resolved = Get-Command -Name $name -ErrorAction SilentlyContinue | Select-Object -First 1
+        if ($resolved.CommandType -eq "Application" -or $name -match '\.exe$') { $programs += $name }
+        elseif ($resolved -or $name -match '^\w+-\w+$') { $cmdlets += $name }
+    }
+    $logNames = [regex]::Matches($text, "LogName\s*=\s*([^;}]+)") | ForEach-Object { [regex]::Matches($_.Groups[1].Value, "'([^']+)'") | ForEach-Object { $_.Groups[1].Value } }
+    [PSCustomObject]@{
+        Task       = $Task.Name
+        Category   = $taskCategories[$Task.Name]
+        Programs   = @($programs | Sort-Object -Unique)
+        Cmdlets    = @($cmdlets | Sort-Object -Unique)
+        WmiClasses = @([regex]::Matches($text, '\b(?:Win32|MSFT|CIM)_\w+') | ForEach-Object Value | Sort-Object -Unique)
+        Registry   = @([regex]::Matches($text, 'HK(?:LM|CU):\\[^''"]+') | ForEach-Object Value | Sort-Object -Unique)
+        EventLogs  = @($logNames | Sort-Object -Unique)
+        Reads      = @([regex]::Matches($text, '(?:C:\\Windows|\$env:\w+)\\[^''"\s]+') | ForEach-Object Value | Sort-Object -Unique)
+        Writes     = @([regex]::Matches($text, 'WindowsForum-[\w-]+(?:\.\w+)?') | ForEach-Object Value | Sort-Object -Unique)
+    }
+}
+# The last five durations of every task that completed, for the time estimates in the window, the console
+# and -TaskInfo. Saved after each collection.
+$durationHistoryPath = Join-Path -Path $env:LOCALAPPDATA -ChildPath "WindowsForum\task-durations.json"
+$durationHistory = @{}
+if (Test-Path -Path $durationHistoryPath) {
+    (Get-Content -Path $durationHistoryPath -Raw | ConvertFrom-Json).PSObject.Properties | ForEach-Object { $durationHistory[$_.Name] = @($_.Value) }
+}
+# Median of the recorded durations, or nothing if the task never completed before
+function Get-WFExpectedSeconds {
+    param([string]$TaskName)
+    $samples = @($durationHistory[$TaskName] | Sort-Object)
+    if ($samples) { $samples[[math]::Floor($samples.Count / 2)] }
+}
+# -TaskInfo: description, category, requirements, durations of the last run and the dry-run plan
+if ($TaskInfo) {
+    $lastRun = Get-Content -Path "$filePath\collection-info.json" -Raw -ErrorAction SilentlyContinue | ConvertFrom-Json
+    $knownTasks = @($diagnosticTasks) + $adminTasks + $arm64Tasks | Group-Object { $_.Name } | ForEach-Object { $_.Group[0] }
+    $matchingTasks = @($knownTasks | Where-Object { $_.Name -like $TaskInfo })
+    if (!$matchingTasks) { Write-Error "No task matches '$TaskInfo'. Tasks: $(($knownTasks.Name | Sort-Object) -join ', ')"; exit 1 }
+    foreach ($task in $matchingTasks) {
+        $plan = Get-WFTaskPlan $task
+        $lastDuration = ($lastRun.Tasks | Where-Object { $_.Task -eq $task.Name }).WallSeconds
+        $requirement = if ($adminTasks.Name -contains $task.Name) { "administrator" } elseif ($arm64Tasks.Name -contains $task.Name) { "Windows on ARM" } else { "none" }
+        "== $($task.Name)"
+        "  Description: $($taskDescriptions[$task.Name])"
+        "  Category:    $($taskCategories[$task.Name])"
+        "  Requires:    $requirement"
+        "  Last run:    $(if ($lastDuration) { "$lastDuration s" } else { "no earlier run recorded" })"
+        if ($durationHistory[$task.Name]) { "  Typical:     $(Get-WFExpectedSeconds $task.Name) s (median of the last $(@($durationHistory[$task.Name]).Count) runs)" }
+        foreach ($kind in "Programs", "Cmdlets", "WmiClasses", "Registry", "EventLogs", "Reads", "Writes") {
+            if ($plan.$kind) { "  {0,-12} {1}" -f "${kind}:", ($plan.$kind -join ', ') }
+        }
+        ""
+    }
+    exit
+}
+# -Doctor: checks the environment so a real run doesn't fail halfway. Exits 1 if anything fails.
+if ($Doctor) {
+    $checks = [System.Collections.Generic.List[object]]::new()
+    $addCheck = { param($Check, $Status, $Detail) $checks.Add([PSCustomObject]@{ Check = $Check; Status = $Status; Detail = $Detail }) }
+    $plan = @($diagnosticTasks + $adminTasks + $arm64Tasks | ForEach-Object { Get-WFTaskPlan $_ })
+    foreach ($program in @($plan.Programs) + "powershell.exe" | Sort-Object -Unique) {
+        $users = (($plan | Where-Object { $_.Programs -contains $program }).Task | Sort-Object -Unique) -join ', '
+        $resolved = Get-Command -Name $program -CommandType Application -ErrorAction SilentlyContinue | Select-Object -First 1
+        if ($resolved) { & $addCheck "Program $program" "OK" $resolved.Source }
+        else { & $addCheck "Program $program" "Fail" "Not found; affects $(if ($users) { $users } else { 'background jobs' })" }
+    }
+    try {
+        $probe = Join-Path $outputFolder "wfdiag-doctor-$PID.tmp"
+        [IO.File]::WriteAllText($probe, "probe")
+        Remove-Item -Path $probe -Force
+        $writable = $true
+    } catch { & $addCheck "Output folder" "Fail" "Cannot write to ${outputFolder}: $($_.Exception.Message)"; $writable = $false }
+    # Free space is only known for drive letters; a share (\\server\share) reports none
+    if ($writable -and $outputFolder.StartsWith("\\")) { & $addCheck "Output folder" "OK" "$outputFolder is writable (network share, free space not checked)" }
+    elseif ($writable) {
+        $freeGB = [math]::Round((New-Object System.IO.DriveInfo($outputFolder.Substring(0, 1))).AvailableFreeSpace / 1GB, 1)
+        & $addCheck "Output folder" $(if ($freeGB -lt 2) { "Warning" } else { "OK" }) "$outputFolder is writable, $freeGB GB free"
+    }
+    $winmgmt = Get-Service -Name winmgmt -ErrorAction SilentlyContinue
+    & $addCheck "WMI service" $(if ($winmgmt.Status -eq "Running") { "OK" } else { "Fail" }) "winmgmt is $(if ($winmgmt) { $winmgmt.Status } else { 'missing' })"
+    try {
+        $elapsed = Measure-Command { $null = Get-CimInstance -ClassName Win32_OperatingSystem -ErrorAction Stop }
+        & $addCheck "WMI query" $(if ($elapsed.TotalSeconds -gt 10) { "Warning" } else { "OK" }) "Win32_OperatingSystem answered in $([math]::Round($elapsed.TotalSeconds, 1)) s"
+    } catch { & $addCheck "WMI query" "Fail" "$($_.Exception.Message) Try 'winmgmt /verifyrepository' from an elevated prompt." }
+    try {
+        $null = New-Object -ComObject Shell.Application
+        & $addCheck "COM" "OK" "Shell.Application created"
+    } catch { & $addCheck "COM" "Fail" $_.Exception.Message }
+    try {
+        $echo = Start-Job -ScriptBlock { "ok" } | Wait-Job -Timeout 60 | Receive-Job
+        & $addCheck "Background jobs" $(if ($echo -eq "ok") { "OK" } else { "Fail" }) $(if ($echo -eq "ok") { "Start-Job works" } else { "A test job did not finish within 60 s" })
+    } catch { & $addCheck "Background jobs" "Fail" $_.Exception.Message }
+    Get-Job | Remove-Job -Force
+    & $addCheck "Administrator" $(if ($isAdmin) { "OK" } else { "Warning" }) $(if ($isAdmin) { "Elevated" } else { "Not elevated; skipped: $(($skippedTasks | Where-Object { $_.Reason -like '*administrator*' }).Task -join ', ')" })
+    if ($Quiet) { @($checks) | ConvertTo-Json -Compress }
+    else { $checks | ForEach-Object { "{0,-8} {1,-24} {2}" -f $_.Status, $_.Check, $_.Detail } }
+    exit $(if ($checks.Status -contains "Fail") { 1 } else { 0 })
+}
+if ($DryRun) {
+    $plan = @($diagnosticTasks | ForEach-Object { Get-WFTaskPlan $_ })
+    if ($Quiet) {
+        [ordered]@{ Tasks = $plan; Skipped = @($skippedTasks) } | ConvertTo-Json -Depth 4 -Compress
+    } else {
+        "Dry run: $($plan.Count) tasks would run, writing to $filePath and $zipFilePath"
+        foreach ($entry in $plan) {
+            ""
+            "== $($entry.Task) [$($entry.Category)]"
+            foreach ($kind in "Programs", "Cmdlets", "WmiClasses", "Registry", "EventLogs", "Reads", "Writes") {
+                if ($entry.$kind) { "  {0,-11} {1}" -f "${kind}:", ($entry.$kind -join ', ') }
+            }
+        }
+        if ($skippedTasks) { ""; "Skipped:"; $skippedTasks | ForEach-Object { "  $($_.Task): $($_.Reason)" } }
+    }
+    exit
+}
+
+# Findings rules run after collection and turn task output into warnings and recommendations.
+# Each Check gets the results folder and its parsed WindowsForum-Snapshot.json and returns zero or more
+# findings created with New-Finding. Rules read only the bundle, never the machine they run on.
+function New-Finding {
+    param([ValidateSet("Info", "Warning", "Critical")][string]$Severity, [string]$Message, [string]$Recommendation, [string]$Evidence)
+    [PSCustomObject]@{ Severity = $Severity; Message = $Message; Recommendation = $Recommendation; Evidence = $Evidence }
+}
+# Short plain-language overview for the top of the summary, meant to be pasted into a forum thread
+function Get-WFNarrative {
+    param([string]$FilePath, $Findings)
+    # Everything comes from the bundle (the snapshot and the minidump summary), not from the machine running this
+    $snapshotFile = Join-Path -Path $FilePath -ChildPath "WindowsForum-Snapshot.json"
+    $snapshot = if (Test-Path -Path $snapshotFile) { Get-Content -Path $snapshotFile -Raw | ConvertFrom-Json }
+    $system = $snapshot.System
+    if ($system) { "$($system.Manufacturer) $($system.Model) with $($system.Processor) and $([math]::Round($snapshot.Memory.RamGB)) GB RAM, running $($system.OS) (build $($snapshot.OSBuild))." }
+    # BSODs in the week before the collection, named from the minidump summary when the dump is still there
+    $bugchecks = @($snapshot.Bugchecks | ForEach-Object { [Convert]::ToUInt32($_.Code, 16) })
+    if ($bugchecks) {
+        $bugcheckNames = @{}
+        $lastCode = $null
+        Get-Content "$FilePath\WindowsForum-MinidumpSummary.txt" -ErrorAction SilentlyContinue | ForEach-Object {
+            if ($_ -match '^Bugcheck\s*:\s*(0x[0-9A-F]+)') { $lastCode = [Convert]::ToUInt32($Matches[1], 16) }
+            elseif ($_ -match '^Name\s*:\s*(\S+)' -and $null -ne $lastCode) { $bugcheckNames[$lastCode] = $Matches[1] }
+        }
+        $top = $bugchecks | Group-Object | Sort-Object Count -Descending | Select-Object -First 1
+        $topName = if ($bugcheckNames[[uint32]$top.Name]) { $bugcheckNames[[uint32]$top.Name] } else { "0x{0:X8}" -f [uint32]$top.Name }
+        $plural = if ($bugchecks.Count -eq 1) { "" } else { "s" }
+        if ($top.Count -eq $bugchecks.Count -and $bugchecks.Count -gt 1) { "$($bugchecks.Count) BSODs in the last week, all $topName." }
+        else { "$($bugchecks.Count) BSOD$plural in the last week, most often $topName." }
+    }
+    $gpuDriver = $snapshot.Drivers | Where-Object { $_.Class -eq 'DISPLAY' -and $_.Provider -ne 'Microsoft' -and $_.Date } | Select-Object -First 1
+    if ($gpuDriver) {
+        $months = [math]::Floor((([datetime]$snapshot.Collected) - [datetime]$gpuDriver.Date).TotalDays / 30.4)
+        "GPU driver for $($gpuDriver.DeviceName) is version $($gpuDriver.Version), $months months old."
+    }
+    $critical = @($Findings | Where-Object { $_.Severity -eq 'Critical' }).Count
+    $warnings = @($Findings | Where-Object { $_.Severity -eq 'Warning' }).Count
+    if ($critical + $warnings -eq 0) { "No critical issues or warnings were found by the automatic checks." }
+    else {
+        $first = @($Findings)[0]
+        "The automatic checks found $critical critical issue(s) and $warnings warning(s); most important: $($first.Message)"
+    }
+}
+# Single self-contained HTML report: summary, findings, per-task results and the text output of every
+# report file in collapsible sections, with a search box and severity colours
+function New-WFHtmlReport {
+    param([string]$FilePath, [string[]]$Narrative, $Findings, $TaskResults)
+    $encode = { param($text) [System.Net.WebUtility]::HtmlEncode("$text") }
+    $maxPreviewChars = 300KB
+    $html = New-Object System.Text.StringBuilder
+    [void]$html.Append(@"
+<!DOCTYPE html>
+<html><head><meta charset="utf-8"><title>WindowsForum.com Diagnostic Report - $(& $encode $env:COMPUTERNAME)</title>
+<style>
+body { font-family: Segoe UI, sans-serif; margin: 1.5em; color: #222; }
+h1 { font-size: 1.4em; } details { border: 1px solid #ccc; border-radius: 4px; margin: .4em 0; padding: .3em .6em; }
+summary { cursor: pointer; font-weight: 600; } pre { white-space: pre-wrap; font-size: .85em; max-height: 40em; overflow: auto; }
+table { border-collapse: collapse; } td, th { border: 1px solid #ddd; padding: .2em .5em; text-align: left; }
+.Critical { background: #fdd; } .Warning { background: #ffe9b3; } .Info { background: #eef5ff; }
+#search { width: 30em; padding: .3em; margin-bottom: 1em; } .hidden { display: none; }
+</style></head><body>
+<h1>WindowsForum.com Diagnostic Tool $version - $(& $encode $env:COMPUTERNAME) - $(Get-Date -Format 'yyyy-MM-dd HH:mm')</h1>
+<input id="search" type="search" placeholder="Search findings and reports...">
+"@)
+    [void]$html.Append("<h2>Overview</h2>")
+    foreach ($line in $Narrative) { [void]$html.Append("<p>$(& $encode $line)</p>") }
+    [void]$html.Append("<h2>Findings</h2><table class=`"searchable`"><tr><th>Severity</th><th>Finding</th><th>Recommendation</th><th>Source</th></tr>")
+    foreach ($finding in $Findings) {
+        [void]$html.Append("<tr class=`"$($finding.Severity)`"><td>$($finding.Severity)</td><td>$(& $encode $finding.Message)</td><td>$(& $encode $finding.Recommendation)</td><td>$(& $encode $finding.Evidence)</td></tr>")
+    }
+    [void]$html.Append("</table><h2>Tasks</h2><table class=`"searchable`"><tr><th>Task</th><th>State</th><th>Severity</th><th>Seconds</th></tr>")
+    foreach ($result in $TaskResults) {
+        [void]$html.Append("<tr class=`"$($result.Severity)`"><td>$(& $encode $result.Task)</td><td>$($result.State)</td><td>$($result.Severity)</td><td>$($result.WallSeconds)</td></tr>")
+    }
+    [void]$html.Append("</table><h2>Reports</h2>")
+    Get-ChildItem -Path $FilePath -Filter "*.txt" -Recurse | Sort-Object Name | ForEach-Object {
+        $text = Get-Content -Path $_.FullName -Raw -ErrorAction SilentlyContinue
+        if ($text.Length -gt $maxPreviewChars) { $text = $text.Substring(0, $maxPreviewChars) + "`n... (truncated, see $($_.Name) in the archive)" }
+        [void]$html.Append("<details class=`"report`"><summary>$(& $encode $_.Name)</summary><pre>$(& $encode $text)</pre></details>")
+    }
+    [void]$html.Append(@"
+<script>
+document.getElementById('search').addEventListener('input', function () {
+  var q = this.value.toLowerCase();
+  document.querySelectorAll('details.report').forEach(function (d) {
+    var hit = !q || d.textContent.toLowerCase().indexOf(q) >= 0;
+    d.classList.toggle('hidden', !hit); d.open = !!q && hit;
+  });
+  document.querySelectorAll('table.searchable tr:not(:first-child)').forEach(function (r) {
+    r.classList.toggle('hidden', !!q && r.textContent.toLowerCase().indexOf(q) < 0);
+  });
+});
+</script></body></html>
+"@)
+    $html.ToString()
+}
+# Concise forum-ready post (Markdown or BBCode for the forum editor): specs, findings and recent errors
+function New-WFForumPost {
+    param([string]$FilePath, [string[]]$Narrative, $Findings, [ValidateSet("Markdown", "BBCode")][string]$Format)
+    $heading = if ($Format -eq "BBCode") { { param($text) "[B]$text[/B]" } } else { { param($text) "### $text" } }
+    # The specs come from the bundle's snapshot, so a post written under -Analyze describes the collecting machine
+    $snapshotFile = Join-Path -Path $FilePath -ChildPath "WindowsForum-Snapshot.json"
+    $snapshot = if (Test-Path -Path $snapshotFile) { Get-Content -Path $snapshotFile -Raw | ConvertFrom-Json }
+    $system = $snapshot.System
+    $specs = [ordered]@{
+        "OS"          = "$($system.OS) (build $($snapshot.OSBuild))"
+        "System"      = "$($system.Manufacturer) $($system.Model)"
+        "Motherboard" = $system.BaseBoard
+        "CPU"         = $system.Processor
+        "RAM"         = "$([math]::Round($snapshot.Memory.RamGB)) GB"
+        "GPU"         = ($system.GPUs | ForEach-Object { "$($_.Name) ($($_.DriverVersion))" }) -join ", "
+        "Disks"       = ($system.DiskDrives | ForEach-Object { "$($_.Model) ($($_.SizeGB) GB)" }) -join ", "
+    }
+    & $heading "System"
+    if ($system) { foreach ($key in $specs.Keys) { "- $($key): $($specs[$key])" } } else { "- No WindowsForum-Snapshot.json in this bundle." }
+    ""
+    & $heading "Overview"
+    $Narrative
+    ""
+    & $heading "Findings"
+    $important = @($Findings | Where-Object { $_.Severity -ne 'Info' })
+    if ($important) { $important | ForEach-Object { "- $($_.Severity): $($_.Message)" } } else { "- No critical issues or warnings found." }
+    ""
+    & $heading "Most frequent errors (last 7 days)"
+    $errors = @($snapshot.ErrorEvents | Sort-Object Count -Descending | Select-Object -First 5)
+    if ($errors) { $errors | ForEach-Object { "- $($_.Provider) event $($_.EventId): $($_.Count)x" } } else { "- None recorded." }
+    ""
+    "Collected with WindowsForum.com Diagnostic Tool $version"
+}
+# Structured results as a SQL script, not a database file: the output is plain text that sqlite3 (or any
+# SQL database) loads, which keeps it readable, redactable and mergeable. Every row carries the computer
+# name and collection time from the bundle's snapshot so bundles from many machines can share one database.
+function Export-WFSql {
+    param([string]$FilePath, $Findings, $TaskResults)
+    $quote = { param($value) if ($null -eq $value) { "NULL" } else { "'" + ("$value" -replace "'", "''") + "'" } }
+    $snapshotFile = Join-Path -Path $FilePath -ChildPath "WindowsForum-Snapshot.json"
+    $snapshot = if (Test-Path -Path $snapshotFile) { Get-Content -Path $snapshotFile -Raw | ConvertFrom-Json }
+    $machine = & $quote $(if ($snapshot.Computer) { $snapshot.Computer } else { $env:COMPUTERNAME })
+    $collected = & $quote $(if ($snapshot.Collected) { $snapshot.Collected } else { (Get-Date).ToString('o') })
+    $insert = { param($table, $values) "INSERT INTO $table VALUES ($machine, $collected, $(($values | ForEach-Object { & $quote $_ }) -join ', '));" }
+    "BEGIN TRANSACTION;"
+    "CREATE TABLE IF NOT EXISTS tasks (computer TEXT, collected TEXT, task TEXT, state TEXT, severity TEXT, wall_seconds REAL, cpu_seconds REAL, peak_memory_mb REAL);"
+    "CREATE TABLE IF NOT EXISTS findings (computer TEXT, collected TEXT, source TEXT, severity TEXT, message TEXT, recommendation TEXT, evidence TEXT);"
+    "CREATE TABLE IF NOT EXISTS events (computer TEXT, collected TEXT, provider TEXT, event_id INTEGER, count INTEGER);"
+    "CREATE TABLE IF NOT EXISTS drivers (computer TEXT, collected TEXT, device TEXT, provider TEXT, version TEXT);"
+    "CREATE TABLE IF NOT EXISTS processes (computer TEXT, collected TEXT, name TEXT, pid INTEGER, cpu_seconds REAL, working_set_mb REAL, path TEXT);"
+    foreach ($result in $TaskResults) { & $insert "tasks" @($result.Task, $result.State, $result.Severity, ($result.WallSeconds -as [double]), $result.CpuSeconds, $result.PeakMemoryMB) }
+    foreach ($finding in $Findings) { & $insert "findings" @($finding.Rule, $finding.Severity, $finding.Message, $finding.Recommendation, $finding.Evidence) }
+    foreach ($errorEvent in $snapshot.ErrorEvents) { & $insert "events" @($errorEvent.Provider, $errorEvent.EventId, $errorEvent.Count) }
+    foreach ($driver in $snapshot.Drivers) { & $insert "drivers" @($driver.DeviceName, $driver.Provider, $driver.Version) }
+    # The process list the Processes task collected (its CSV copy, which -Sql turns on)
+    $processes = Join-Path -Path $FilePath -ChildPath "WindowsForum-RunningProcesses.csv"
+    if (Test-Path -Path $processes) {
+        foreach ($process in Import-Csv -Path $processes) {
+            & $insert "processes" @($process.ProcessName, $process.Id, ($process.CPU -as [double]), $(if ($process.WorkingSet64) { [math]::Round([double]$process.WorkingSet64 / 1MB, 1) }), $process.Path)
+        }
+    }
+    "COMMIT;"
+}
+# WindowsForum-Report.json: the whole diagnostic report (system, task results, findings, summary) as
+# one JSON document for tools that do not want to parse the text reports
+function New-WFJsonReport {
+    param([string]$FilePath, $Narrative, $Findings, $TaskResults)
+    # The system block describes the bundle's snapshot, which -Analyze and -RedactArchive rely on
+    $snapshotFile = Join-Path -Path $FilePath -ChildPath "WindowsForum-Snapshot.json"
+    $snapshot = if (Test-Path -Path $snapshotFile) { Get-Content -Path $snapshotFile -Raw | ConvertFrom-Json }
+    [ordered]@{
+        Tool      = "WindowsForum.com Diagnostic Tool"
+        Version   = $version
+        Collected = if ($snapshot) { $snapshot.Collected } else { (Get-Date).ToString('o') }
+        System    = if ($snapshot) {
+            [ordered]@{
+                ComputerName = $snapshot.Computer
+                Manufacturer = $snapshot.System.Manufacturer
+                Model        = $snapshot.System.Model
+                Processor    = $snapshot.System.Processor
+                MemoryGB     = $snapshot.Memory.RamGB
+                OS           = $snapshot.System.OS
+                Build        = ($snapshot.OSBuild -split '\.')[0]
+                Architecture = $snapshot.System.Architecture
+                Elevated     = $snapshot.System.Elevated
+            }
+        }
+        Summary   = [ordered]@{
+            Overview = @($Narrative)
+            Critical = @($Findings | Where-Object { $_.Severity -eq 'Critical' }).Count
+            Warning  = @($Findings | Where-Object { $_.Severity -eq 'Warning' }).Count
+            Info     = @($Findings | Where-Object { $_.Severity -eq 'Info' }).Count
+        }
+        Tasks     = @($TaskResults | ForEach-Object {
+            [ordered]@{ Task = $_.Task; State = $_.State; Severity = $_.Severity; Started = $(if ($_.Started) { $_.Started.ToString('o') }); WallSeconds = $_.WallSeconds -as [double]
+                CpuSeconds = $_.CpuSeconds; PeakMemoryMB = $_.PeakMemoryMB; DiskWrittenMB = $_.DiskWrittenMB; Findings = @($_.Findings).Count }
+        })
+        Findings  = @($Findings | ForEach-Object { [ordered]@{ Source = $_.Rule; Severity = $_.Severity; Message = $_.Message; Recommendation = $_.Recommendation; Evidence = $_.Evidence } })
+    } | ConvertTo-Json -Depth 5
+}
+# collection-info.json: how this bundle was produced (tool version, command line, elevation,
+# architecture, per-task durations, skipped tasks), which helpers cannot tell from the reports alone
+function New-WFCollectionInfo {
+    param($TaskResults, $SkippedTasks, $Parameters, [datetime]$Started)
+    # Rebuilt from the bound parameters rather than the raw command line, so the archive password and the
+    # upload URL are masked however they were typed (-Pass, -Password:x, quoted)
+    $recorded = [ordered]@{}
+    $Parameters.GetEnumerator() | ForEach-Object { $recorded[$_.Key] = if ($_.Key -in "Password", "ProtectedPassword", "UploadUrl") { "***" } else { $_.Value } }
+    $toolName = Split-Path -Path $(if ($PSCommandPath) { $PSCommandPath } else { [System.Diagnostics.Process]::GetCurrentProcess().MainModule.FileName }) -Leaf
+    [ordered]@{
+        Tool         = "WindowsForum.com Diagnostic Tool"
+        Version      = $version
+        CommandLine  = "$toolName $(ConvertTo-WFArgumentString $recorded)".Trim()
+        Parameters   = @($Parameters.Keys | Sort-Object)
+        Elevated     = $isAdmin
+        Architecture = $env:PROCESSOR_ARCHITECTURE
+        PowerShell   = "$($PSVersionTable.PSVersion)"
+        OSBuild      = [Environment]::OSVersion.Version.ToString()
+        Started      = $Started.ToString('o')
+        Finished     = (Get-Date).ToString('o')
+        Tasks        = @($TaskResults | ForEach-Object { [ordered]@{ Task = $_.Task; State = $_.State; WallSeconds = $_.WallSeconds -as [double] } })
+        SkippedTasks = @($SkippedTasks | ForEach-Object { [ordered]@{ Task = $_.Task; Reason = $_.Reason } })
+    } | ConvertTo-Json -Depth 4
+}
+# SARIF 2.1.0 export of the findings. Rule ids are derived from the rule or task name (e.g.
+# "wf.low-disk-space") so they stay stable between versions; evidence files become artifact locations.
+function Export-WFSarif {
+    param($Findings)
+    $ruleId = { param($name) "wf." + ($name.ToLower() -replace '[^a-z0-9]+', '-').Trim('-') }
+    $level = @{ Critical = "error"; Warning = "warning"; Info = "note" }
+    $rules = @($Findings | ForEach-Object { $_.Rule } | Sort-Object -Unique | ForEach-Object { [ordered]@{ id = & $ruleId $_; name = $_ } })
+    $results = @($Findings | ForEach-Object {
+        $result = [ordered]@{
+            ruleId     = & $ruleId $_.Rule
+            level      = $level[$_.Severity]
+            message    = [ordered]@{ text = $_.Message }
+            properties = [ordered]@{ severity = $_.Severity; recommendation = $_.Recommendation }
+        }
+        if ($_.Evidence) {
+            $result.locations = @([ordered]@{ physicalLocation = [ordered]@{ artifactLocation = [ordered]@{ uri = ($_.Evidence -replace '\\', '/') } } })
+        }
+        $result
+    })
+    [ordered]@{
+        '$schema' = "https://json.schemastore.org/sarif-2.1.0.json"
+        version   = "2.1.0"
+        runs      = @([ordered]@{
+            tool    = [ordered]@{ driver = [ordered]@{ name = "wfdiag"; fullName = "WindowsForum.com Diagnostic Tool"; version = $version; rules = $rules } }
+            results = $results
+        })
+    } | ConvertTo-Json -Depth 10
+}
+# -Redact: replaces personal identifiers in every text report before archiving. Binary files (event
+# logs, minidumps) cannot be scrubbed and are listed in WindowsForum-Redaction.txt instead.
+$redactedExtensions = @(".txt", ".log", ".json", ".csv", ".md", ".html", ".htm", ".xml", ".nfo", ".sql", ".reg", ".sarif", ".ndjson")
+function Invoke-WFRedaction {
+    param([string]$FilePath)
+    # Literal identifiers of the machine that collected the bundle, taken from the bundle's own files (it may come
+    # from another PC) and replaced wherever they appear as a whole word
+    $readBundleJson = {
+        param($name)
+        $file = Get-ChildItem -Path $FilePath -Filter $name -Recurse -File | Select-Object -First 1
+        # Returned through a variable so the records of a JSON array come out one by one
+        if ($file) { $json = Get-Content -Path $file.FullName -Raw | ConvertFrom-Json; $json }
+    }
+    $snapshot = & $readBundleJson "WindowsForum-Snapshot.json"
+    $computerSystem = @(& $readBundleJson "WindowsForum-CompSystem.json") | Select-Object -First 1
+    $report = & $readBundleJson "WindowsForum-Report.json"
+    # Without them only MAC and public IP addresses could be scrubbed, which is not what -Redact promises
+    if (!$snapshot -and !$computerSystem -and !$report) { throw "$FilePath has no WindowsForum-Snapshot.json, WindowsForum-CompSystem.json or WindowsForum-Report.json to take the computer and user names from" }
+    $identifiers = [ordered]@{}
+    # Built-in accounts and profile folders are the same on every PC, and scrubbing them would hide useful detail
+    $builtInNames = "Administrator", "Guest", "DefaultAccount", "WDAGUtilityAccount", "defaultuser0", "Public", "Default", "Default User", "All Users"
+    $userNames = @($computerSystem.UserName, $computerSystem.PrimaryOwnerName | ForEach-Object { "$_".Split('\')[-1] })
+    $textFiles = @(Get-ChildItem -Path $FilePath -Recurse -File | Where-Object { $redactedExtensions -contains $_.Extension.ToLower() })
+    foreach ($file in $textFiles) {
+        $text = [System.IO.File]::ReadAllText($file.FullName)
+        # Profile folders (C:\Users\name\..., also with the doubled backslashes of JSON) name the users of the machine
+        $userNames += @([regex]::Matches($text, '(?i)\b[A-Z]:\\{1,2}Users\\{1,2}([^\\/:*?"<>|\r\n]+?)\\') | ForEach-Object { $_.Groups[1].Value })
+        # Wi-Fi names as WLAN events report them ("SSID: name"); the label is not translated
+        foreach ($match in [regex]::Matches($text, '\bSSID\b[^:\r\n]{0,20}:\s*([^\r\n"\\]+?)\s*(?:$|\r|\n|"|\\)', 'Multiline')) { $identifiers[$match.Groups[1].Value] = "<WIFI>" }
+    }
+    foreach ($name in $userNames) { if ($name -and $builtInNames -notcontains $name) { $identifiers[$name] = "<USER>" } }
+    $domain = if ($computerSystem.PartOfDomain) { $computerSystem.Domain }
+    foreach ($name in $snapshot.Computer, $computerSystem.Name, $report.System.ComputerName, $domain) { if ($name) { $identifiers["$name"] = "<COMPUTER>" } }
+    $serials = @("WindowsForum-BIOS.json", "WindowsForum-BaseBoard.json", "WindowsForum-DiskDrive.json" | ForEach-Object { & $readBundleJson $_ } | ForEach-Object { "$($_.SerialNumber)".Trim() })
+    # Placeholder serials such as "To be filled by O.E.M." or "Default string" are left alone
+    foreach ($serial in $serials) { if ($serial.Length -ge 5 -and $serial -notmatch 'O\.E\.M|Default|System Serial|None|^0+$') { $identifiers[$serial] = "<SERIAL>" } }
+    # Longest first, so "DESKTOP-PC" is not half-replaced by a shorter "DESKTOP" account name
+    $literals = @($identifiers.Keys | Where-Object { $_.Length -ge 3 } | Sort-Object Length -Descending)
+    $macPattern = '(?<![0-9A-Fa-f:-])(?:[0-9A-Fa-f]{2}[:-]){5}[0-9A-Fa-f]{2}(?![0-9A-Fa-f:-])'
+    $ipPattern = '(?<![\d.])(\d{1,3})\.(\d{1,3})\.(\d{1,3})\.(\d{1,3})(?![\d.])'
+    # Only public addresses are personal; private, loopback and link-local ranges help troubleshooting
+    $ipEvaluator = {
+        param($match)
+        $octets = 1..4 | ForEach-Object { [int]$match.Groups[$_].Value }
+        if ($octets | Where-Object { $_ -gt 255 }) { return $match.Value }
+        $private = $octets[0] -in 0, 10, 127 -or $octets[0] -ge 224 -or ($octets[0] -eq 169 -and $octets[1] -eq 254) -or
+            ($octets[0] -eq 172 -and $octets[1] -ge 16 -and $octets[1] -le 31) -or ($octets[0] -eq 192 -and $octets[1] -eq 168) -or
+            ($octets[0] -eq 100 -and $octets[1] -ge 64 -and $octets[1] -le 127)
+        if ($private) { $match.Value } else { "<PUBLIC-IP>" }
+    }
+    $counts = @{}
+    $skipped = @()
+    foreach ($file in Get-ChildItem -Path $FilePath -Recurse -File) {
+        if ($redactedExtensions -notcontains $file.Extension.ToLower()) { $skipped += $file.FullName.Substring($FilePath.Length + 1); continue }
+        $reader = New-Object System.IO.StreamReader($file.FullName, $true)
+        try { $text = $reader.ReadToEnd(); $encoding = $reader.CurrentEncoding } finally { $reader.Dispose() }
+        $original = $text
+        foreach ($literal in $literals) {
+            $pattern = '(?<![\w])' + [regex]::Escape($literal) + '(?![\w])'
+            $hits = [regex]::Matches($text, $pattern, 'IgnoreCase').Count
+            if ($hits) { $counts[$identifiers[$literal]] += $hits; $text = [regex]::Replace($text, $pattern, $identifiers[$literal], 'IgnoreCase') }
+        }
+        $hits = [regex]::Matches($text, $macPattern).Count
+        if ($hits) { $counts["<MAC>"] += $hits; $text = [regex]::Replace($text, $macPattern, "<MAC>") }
+        $text = [regex]::Replace($text, $ipPattern, [System.Text.RegularExpressions.MatchEvaluator]$ipEvaluator)
+        $counts["<PUBLIC-IP>"] += ([regex]::Matches($text, '<PUBLIC-IP>').Count - [regex]::Matches($original, '<PUBLIC-IP>').Count)
+        if ($text -ne $original) { [System.IO.File]::WriteAllText($file.FullName, $text, $encoding) }
+    }
+    "Personal identifiers were replaced in all text reports of this bundle:"
+    foreach ($token in "<USER>", "<COMPUTER>", "<SERIAL>", "<WIFI>", "<MAC>", "<PUBLIC-IP>") { "  {0,-12} {1} replacements" -f $token, [int]$counts[$token] }
+    ""
+    "Private IP addresses (192.168.x.x, 10.x.x.x, ...) are kept. Four-part version numbers that look like"
+    "public IP addresses may also be replaced."
+    ""
+    "These binary files could not be redacted and may still contain personal data:"
+    if ($skipped) { $skipped | ForEach-Object { "  $_" } } else { "  (none)" }
+}
+# Uploads a file as multipart/form-data ("file" field) and returns the share link from the response:
+# the url/link/share_url property of a JSON reply, or the first URL in a plain-text reply
+function Send-WFArchive {
+    param([string]$Path, [string]$Url, [scriptblock]$OnProgress)
+    if (([uri]$Url).Scheme -ne "https") { throw "Upload endpoint must use HTTPS: $Url" }
+    [System.Net.ServicePointManager]::SecurityProtocol = [System.Net.ServicePointManager]::SecurityProtocol -bor [System.Net.SecurityProtocolType]::Tls12
+    $boundary = [guid]::NewGuid().ToString("N")
+    $head = [System.Text.Encoding]::UTF8.GetBytes("--$boundary`r`nContent-Disposition: form-data; name=`"file`"; filename=`"$(Split-Path -Path $Path -Leaf)`"`r`nContent-Type: application/octet-stream`r`n`r`n")
+    $tail = [System.Text.Encoding]::UTF8.GetBytes("`r`n--$boundary--`r`n")
+    $request = [System.Net.HttpWebRequest]::Create($Url)
+    $request.Method = "POST"
+    $request.ContentType = "multipart/form-data; boundary=$boundary"
+    $request.UserAgent = "wfdiag/$version"
+    $request.AllowWriteStreamBuffering = $false
+    $request.Timeout = 30 * 60 * 1000
+    $file = [System.IO.File]::OpenRead($Path)
+    try {
+        $request.ContentLength = $head.Length + $file.Length + $tail.Length
+        $body = $request.GetRequestStream()
+        try {
+            $body.Write($head, 0, $head.Length)
+            $buffer = New-Object byte[] 256KB
+            while (($read = $file.Read($buffer, 0, $buffer.Length)) -gt 0) {
+                $body.Write($buffer, 0, $read)
+                if ($OnProgress) { & $OnProgress ([int](100 * $file.Position / [math]::Max($file.Length, 1))) }
+            }
+            $body.Write($tail, 0, $tail.Length)
+        } finally { $body.Dispose() }
+    } finally { $file.Dispose() }
+    $response = $request.GetResponse()
+    try { $text = (New-Object System.IO.StreamReader($response.GetResponseStream())).ReadToEnd() } finally { $response.Dispose() }
+    $reply = try { $text | ConvertFrom-Json } catch { $null }
+    $link = if ($reply) { @($reply.url, $reply.link, $reply.share_url) | Where-Object { $_ } | Select-Object -First 1 }
+    if (!$link -and $text -match 'https?://\S+') { $link = $Matches[0] }
+    $link
+}
+# Maps the file names (without extension, since Out-TaskTable and Export-CimClass add .txt, .csv and .json)
+# and folders a task writes to the task's name
+function Get-WFTaskProducers {
+    param($Tasks)
+    $producers = @{}
+    foreach ($task in $Tasks) {
+        foreach ($match in [regex]::Matches($task.Task.ToString(), 'WindowsForum-[\w-]+')) { $producers[$match.Value] = $task.Name }
+    }
+    # Names built at run time
+    $producers["Minidump"] = "BSOD Minidump"
+    $producers["WindowsForum-System"] = "Event Logs"
+    $producers["WindowsForum-Application"] = "Event Logs"
+    $producers
+}
+# manifest.json: every file in the bundle with its size, SHA-256 and the task that wrote it, so a
+# bundle can be checked for tampering or missing files and ingested automatically. The producing
+# task is found from the WindowsForum-*.* names in each task's script; anything else is the tool's own.
+# tool-logs\wfdiag.log is left out: archiving, upload and retention still write to it after the hashes are taken.
+function New-WFManifest {
+    param([string]$FilePath, $Tasks, [datetime]$Started)
+    $producers = Get-WFTaskProducers $Tasks
+    $root = (Resolve-Path -Path $FilePath).Path.TrimEnd('\') + '\'
+    $files = foreach ($file in Get-ChildItem -Path $root -Recurse -File | Where-Object { $_.Name -ne "manifest.json" }) {
+        $relative = $file.FullName.Substring($root.Length)
+        if ($relative -eq "tool-logs\wfdiag.log") { continue }
+        $task = $producers[$file.BaseName]
+        if (!$task) { $task = $producers[$relative.Split('\')[0]] }
+        [ordered]@{
+            Path     = $relative.Replace('\', '/')
+            Size     = $file.Length
+            SHA256   = (Get-FileHash -Path $file.FullName -Algorithm SHA256).Hash.ToLower()
+            Task     = if ($task) { $task } else { "wfdiag" }
+            Modified = $file.LastWriteTime.ToString('o')
+        }
+    }
+    [ordered]@{
+        Tool      = "WindowsForum.com Diagnostic Tool"
+        Version   = $version
+        Started   = $Started.ToString('o')
+        Finished  = (Get-Date).ToString('o')
+        FileCount = @($files).Count
+        Files     = @($files)
+    } | ConvertTo-Json -Depth 4
+}
+# -RedactArchive: scrubs an archive collected earlier (for users who decide to redact only after
+# collecting) and writes <name>-redacted.zip next to it, with a fresh manifest
+if ($RedactArchive) {
+    $source = (Resolve-Path -Path $RedactArchive).Path
+    $destination = ($source -replace '\.zip$', '') + "-redacted.zip"
+    $workPath = Join-Path -Path $env:TEMP -ChildPath "wfdiag-redact-$([guid]::NewGuid().ToString('N'))"
+    try {
+        [System.IO.Compression.ZipFile]::ExtractToDirectory($source, $workPath)
+        $redactionReport = try { Invoke-WFRedaction $workPath } catch { Write-Error "Cannot redact ${RedactArchive}: $($_.Exception.Message)"; exit 1 }
+        $redactionReport | Out-File "$workPath\WindowsForum-Redaction.txt"
+        if (Test-Path -Path "$workPath\manifest.json") {
+            $started = [datetime](Get-Content -Path "$workPath\manifest.json" -Raw | ConvertFrom-Json).Started
+            New-WFManifest $workPath ($diagnosticTasks + $adminTasks + $arm64Tasks) $started | Out-File "$workPath\manifest.json" -Encoding utf8
+        }
+        if (Test-Path -Path $destination) { Remove-Item -Path $destination -Force }
+        New-WFZip $workPath $destination "Optimal"
+    } finally {
+        Remove-Item -Path $workPath -Recurse -Force -ErrorAction SilentlyContinue
+    }
+    "Redacted copy written to $destination"
+    $redactionReport
+    exit
+}
+$findingRules = @(
+    @{ Name = "Low Disk Space"; Check = { param($filePath, $snapshot)
+        $snapshot.Disks | Where-Object { $_.SizeGB -gt 0 } | ForEach-Object {
+            if ($_.FreeGB / $_.SizeGB -lt 0.1 -or $_.FreeGB -lt 10) {
+                New-Finding "Warning" "Drive $($_.Drive) has only $($_.FreeGB) GB free ($([math]::Round(100 * $_.FreeGB / $_.SizeGB))%)." "Free up space on $($_.Drive); updates and page files need room to work." "WindowsForum-DiskPartition.txt"
+            }
+        } }},
+    @{ Name = "Pending Reboot"; Check = { param($filePath, $snapshot)
+        if ($snapshot.RebootPending) { New-Finding "Warning" "A reboot is pending to finish installing updates or changes." "Restart the computer before further troubleshooting." "WindowsForum-WindowsUpdate.txt" } }},
+    @{ Name = "Outdated BIOS"; Check = { param($filePath, $snapshot)
+        $bios = $snapshot.BIOS
+        if ($bios.ReleaseDate -and [datetime]$bios.ReleaseDate -lt ([datetime]$snapshot.Collected).AddYears(-3)) {
+            New-Finding "Info" "BIOS $($bios.Version) was released on $(([datetime]$bios.ReleaseDate).ToString('yyyy-MM-dd'))." "Check the PC or motherboard manufacturer's site for a newer BIOS/UEFI version." "WindowsForum-BIOS.txt"
+        } }},
+    @{ Name = "Failed Services"; Check = { param($filePath, $snapshot)
+        $snapshot.FailedServices | ForEach-Object {
+            New-Finding "Warning" "Automatic service '$($_.Name)' is $("$($_.State)".ToLower()) with exit code $($_.ExitCode)." "Check the System event log for errors from this service." "WindowsForum-SystemServices.txt"
+        } }},
+    @{ Name = "SMART Status"; Check = { param($filePath, $snapshot)
+        $snapshot.DiskHealth | ForEach-Object {
+            if ($_.Status -eq "SMART") { New-Finding "Critical" "SMART predicts failure for disk $($_.Disk)." "Back up your data now and replace the disk." "WindowsForum-DiskDrive.txt" }
+            else { New-Finding "Critical" "Disk '$($_.Disk)' reports health status $($_.Status)." "Back up your data and check the disk with the manufacturer's tool." "WindowsForum-DiskDrive.txt" }
+        } }},
+    @{ Name = "Driver Age"; Check = { param($filePath, $snapshot)
+        $maxDriverAgeYears = 5
+        $drivers = $snapshot.Drivers
+        # Inbox drivers carry deliberately old dates, so only third-party drivers are aged
+        $oldDrivers = $drivers | Where-Object { $_.Provider -ne 'Microsoft' -and $_.Date -and [datetime]$_.Date -lt ([datetime]$snapshot.Collected).AddYears(-$maxDriverAgeYears) } |
+            Sort-Object DeviceName -Unique
+        if ($oldDrivers) {
+            $names = ($oldDrivers | Select-Object -First 10 | ForEach-Object { "$($_.DeviceName) ($(([datetime]$_.Date).ToString('yyyy-MM-dd')))" }) -join "; "
+            New-Finding "Info" "$(@($oldDrivers).Count) third-party drivers are older than $maxDriverAgeYears years: $names" "Check the device manufacturers' sites for current drivers." "WindowsForum-DriversList.txt"
+        }
+        $drivers | Where-Object { $_.Signed -eq $false } | ForEach-Object {
+            New-Finding "Warning" "Driver for '$($_.DeviceName)' ($($_.Version)) is not signed." "Replace it with a signed driver from the device manufacturer." "WindowsForum-DriversList.txt"
+        }
+        # Devices that fell back to a generic inbox driver instead of the OEM one
+        $drivers | Where-Object { $_.Class -in 'DISPLAY', 'MEDIA' -and $_.Provider -eq 'Microsoft' -and $_.DeviceName -match 'Basic Display|High Definition Audio Device' } | ForEach-Object {
+            New-Finding "Warning" "'$($_.DeviceName)' is using the generic Microsoft inbox driver." "Install the manufacturer's driver for this device." "WindowsForum-DriversList.txt"
+        } }},
+    @{ Name = "Windows Build"; Check = { param($filePath, $snapshot)
+        # Feature releases and the end of servicing for Home/Pro editions; update when a new release ships
+        $windowsReleases = @{
+            19045 = @{ Release = "Windows 10 22H2"; EndOfServicing = [datetime]"2025-10-14" }
+            22000 = @{ Release = "Windows 11 21H2"; EndOfServicing = [datetime]"2023-10-10" }
+            22621 = @{ Release = "Windows 11 22H2"; EndOfServicing = [datetime]"2024-10-08" }
+            22631 = @{ Release = "Windows 11 23H2"; EndOfServicing = [datetime]"2025-11-11" }
+            26100 = @{ Release = "Windows 11 24H2"; EndOfServicing = [datetime]"2026-10-13" }
+            26200 = @{ Release = "Windows 11 25H2"; EndOfServicing = [datetime]"2027-10-12" }
+        }
+        if (!$snapshot.OSBuild) { return }
+        $collected = [datetime]$snapshot.Collected
+        $build = [int]($snapshot.OSBuild -split '\.')[0]
+        # LTSC and Server editions share build numbers with Home/Pro (or have their own) but are serviced for years longer
+        $longTermServicing = "$($snapshot.System.OS)" -match 'LTS[BC]|Server'
+        $release = if (!$longTermServicing) { $windowsReleases[$build] }
+        # The dates are those of Home/Pro; Enterprise and Education get 36 months for the H2 releases
+        $homeOrPro = "$($snapshot.System.OS)" -notmatch 'Enterprise|Education'
+        if ($release -and $homeOrPro -and $release.EndOfServicing -lt $collected) {
+            New-Finding "Warning" "$($release.Release) (build $($snapshot.OSBuild)) reached end of servicing for Home/Pro on $($release.EndOfServicing.ToString('yyyy-MM-dd'))." "Upgrade to a supported Windows release to keep receiving security and reliability fixes." "WindowsForum-SystemInfo.txt"
+        } elseif (!$release -and !$longTermServicing -and $build -lt ($windowsReleases.Keys | Measure-Object -Maximum).Maximum) {
+            New-Finding "Warning" "Windows build $($snapshot.OSBuild) is an old, unsupported release." "Upgrade to a supported Windows release." "WindowsForum-SystemInfo.txt"
+        }
+        # Cumulative updates ship monthly, so the age of the newest update shows how far behind the machine is
+        $lastUpdate = $snapshot.LastUpdate
+        if ($lastUpdate) {
+            $installedOn = [datetime]$lastUpdate.InstalledOn
+            $monthsBehind = [math]::Floor(($collected - $installedOn).TotalDays / 30)
+            if ($monthsBehind -ge 2) {
+                New-Finding "Warning" "The newest installed update ($($lastUpdate.HotFixID)) is from $($installedOn.ToString('yyyy-MM-dd')); the machine is about $monthsBehind cumulative updates behind." "Run Windows Update and install all available cumulative updates." "WindowsForum-WindowsUpdate.txt"
+            }
+        } }},
+    @{ Name = "Memory Pressure"; Check = { param($filePath, $snapshot)
+        $memory = $snapshot.Memory
+        if (!$memory) { return }
+        if ($memory.PageFileDisabled) {
+            New-Finding "Warning" "The page file is disabled ($($memory.RamGB) GB RAM)." "Re-enable a system-managed page file; without one, apps crash when memory runs out and no crash dumps can be written." "WindowsForum-CompSystem.txt"
+        }
+        if ($memory.RamGB -lt 8) {
+            New-Finding "Warning" "Only $($memory.RamGB) GB of RAM is installed." "Upgrade to at least 8 GB (16 GB for gaming or heavy multitasking)." "WindowsForum-PhysicalMemory.txt"
+        }
+        if ($memory.CommitPercent -ge 85) {
+            New-Finding "Warning" "Committed memory is at $($memory.CommitPercent)% of the commit limit ($($memory.RamGB) GB RAM plus page file)." "Close memory-hungry programs, let Windows manage the page file size, or add RAM." "WindowsForum-PerformanceData.txt"
+        } }},
+    # Configuration heuristics: not a malware verdict, just items worth a second look
+    @{ Name = "HOSTS Redirections"; Check = { param($filePath, $snapshot)
+        $commonDomains = 'microsoft\.com|windowsupdate\.com|windows\.com|live\.com|office\.com|bing\.com|google\.com|youtube\.com|facebook\.com|apple\.com|amazon\.com|paypal\.com|windowsforum\.com'
+        Get-Content "$filePath\WindowsForum-HostsFile.txt" -ErrorAction SilentlyContinue | ForEach-Object {
+            $entry = ($_ -replace '#.*$', '').Trim() -split '\s+'
+            if ($entry.Count -ge 2 -and ($entry[1..($entry.Count - 1)] -match "(^|\.)($commonDomains)$")) {
+                $action = if ($entry[0] -in '0.0.0.0', '127.0.0.1', '::1') { "blocks" } else { "redirects to $($entry[0])" }
+                New-Finding "Info" "Review: the HOSTS file $action $($entry[1..($entry.Count - 1)] -join ', ')." "Remove the entry unless you added it on purpose; blocked Microsoft domains break Windows Update and activation." "WindowsForum-HostsFile.txt"
+            }
+        } }},
+    @{ Name = "DNS Servers"; Check = { param($filePath, $snapshot)
+        $knownResolvers = '8.8.8.8', '8.8.4.4', '1.1.1.1', '1.0.0.1', '9.9.9.9', '149.112.112.112', '208.67.222.222', '208.67.220.220', '94.140.14.14', '94.140.15.15'
+        $snapshot.DnsServers | ForEach-Object {
+            $alias = $_.Interface
+            $_.Servers | Where-Object { $_ -notin $knownResolvers -and $_ -notmatch '^(10\.|127\.|169\.254\.|192\.168\.|172\.(1[6-9]|2\d|3[01])\.|100\.(6[4-9]|[7-9]\d|1[01]\d|12[0-7])\.)' } | ForEach-Object {
+                New-Finding "Info" "Review: '$alias' uses DNS server $_, which is neither a local router nor a well-known public resolver." "Make sure you recognise this DNS server (it may be your ISP's); unexpected DNS servers can redirect web traffic." "WindowsForum-NetworkConfig.txt"
+            }
+        } }},
+    @{ Name = "Unsigned Autoruns"; Check = { param($filePath, $snapshot)
+        $snapshot.StartupItems | Where-Object { $_.UnsignedInUserFolder } | ForEach-Object {
+            New-Finding "Info" "Review: startup item '$($_.Name)' runs unsigned $($_.Executable) from a user-writable folder." "Check that you recognise this program; remove it from startup if you don't." "WindowsForum-StartupCmd.txt"
+        } }},
+    @{ Name = "Antivirus Disabled"; Check = { param($filePath, $snapshot)
+        $antivirus = $snapshot.Antivirus
+        if ($antivirus -and $antivirus.DefenderRealTime -eq $false -and !$antivirus.Others) {
+            New-Finding "Warning" "Microsoft Defender real-time protection is off and no other antivirus is active." "Turn real-time protection back on in Windows Security unless this is intentional." "WindowsForum-SystemServices.txt"
+        } }},
+    @{ Name = "Baseline Drift"; Check = { param($filePath, $snapshot)
+        $drift = Get-Content "$filePath\WindowsForum-Drift.txt" -ErrorAction SilentlyContinue
+        if ($drift -and $drift[1] -ne "No changes.") {
+            New-Finding "Info" "$($drift[0]) see WindowsForum-Drift.txt for new drivers, services, updates and errors." "If the problem started recently, start with the changes listed in WindowsForum-Drift.txt." "WindowsForum-Drift.txt"
+        } }}
+)
+# Runs every rule against a results folder and tags each finding with the rule's name
+function Invoke-WFFindingRules {
+    param([string]$FilePath)
+    $snapshotFile = Join-Path -Path $FilePath -ChildPath "WindowsForum-Snapshot.json"
+    $snapshot = if (Test-Path -Path $snapshotFile) { Get-Content -Path $snapshotFile -Raw | ConvertFrom-Json }
+    foreach ($rule in $findingRules) {
+        try {
+            & $rule.Check $FilePath $snapshot | ForEach-Object { $_ | Add-Member -NotePropertyName Rule -NotePropertyValue $rule.Name -PassThru }
+        } catch {
+            Write-ToolLog "Finding rule $($rule.Name) failed: $_" "ERROR"
+        }
+    }
 }
 
 # Create and configure the progress bar form
@@ -96,116 +2432,765 @@ $form.TopMost = $true
 $form.FormBorderStyle = [System.Windows.Forms.FormBorderStyle]::FixedSingle
 $form.MaximizeBox = $false
 $form.MinimizeBox = $false
-$form.Size = New-Object System.Drawing.Size(500, 200) # Adjust the size of the form here
+$form.Size = New-Object System.Drawing.Size(820, 460) # Adjust the size of the form here
 
 $label = New-Object System.Windows.Forms.Label
-$label.Location = New-Object System.Drawing.Point(10, 50)
-$label.Size = New-Object System.Drawing.Size(450, 140) # Adjust the size of the label here
-$label.Text = "Initializing..."
+$label.Location = New-Object System.Drawing.Point(10, 40)
+$label.Size = New-Object System.Drawing.Size(615, 40) # Adjust the size of the label here
+$label.Text = Get-WFText Initializing
 $form.Controls.Add($label)
 
+# Stop ends the running tasks and skips the rest but, unlike closing the window, still builds the archive
+$stopButton = New-Object System.Windows.Forms.Button
+$stopButton.Text = Get-WFText Stop
+$stopButton.Location = New-Object System.Drawing.Point(720, 40)
+$stopButton.Add_Click({
+    $script:stopRequested = $true
+    $stopButton.Enabled = $false
+    $label.Text = Get-WFText Stopping
+})
+$form.Controls.Add($stopButton)
+# Pause lets the running tasks finish but starts no new ones until Resume is clicked
+$pauseButton = New-Object System.Windows.Forms.Button
+$pauseButton.Text = Get-WFText Pause
+$pauseButton.Location = New-Object System.Drawing.Point(635, 40)
+$pauseButton.Add_Click({ Switch-WFPause })
+$form.Controls.Add($pauseButton)
+# Shown at the end when tasks failed: runs the tool again with -Resume, which repeats only the tasks that
+# didn't complete and rebuilds the archive
+$retryButton = New-Object System.Windows.Forms.Button
+$retryButton.Text = Get-WFText RetryFailed
+$retryButton.Location = New-Object System.Drawing.Point(635, 40)
+$retryButton.Size = New-Object System.Drawing.Size(160, 23)
+$retryButton.Visible = $false
+$retryButton.Add_Click({
+    if ($PSCommandPath) { Start-Process -FilePath "powershell.exe" -ArgumentList "-NoProfile -ExecutionPolicy Bypass -File `"$PSCommandPath`" $retryArguments" }
+    else { Start-Process -FilePath ([System.Diagnostics.Process]::GetCurrentProcess().MainModule.FileName) -ArgumentList $retryArguments }
+    $form.Close()
+})
+$form.Controls.Add($retryButton)
+function Switch-WFPause {
+    $script:paused = !$script:paused
+    $pauseButton.Text = if ($script:paused) { Get-WFText Resume } else { Get-WFText Pause }
+    Write-ToolLog $(if ($script:paused) { "Collection paused by user" } else { "Collection resumed" })
+    if ($Console) { if ($script:paused) { "Paused: running tasks finish, no new ones start. Press P to resume." } else { "Resumed." } }
+}
+
 # Create and configure the progress bar
 $progressBar = New-Object System.Windows.Forms.ProgressBar
 $progressBar.Location = New-Object System.Drawing.Point(10, 10)
-$progressBar.Size = New-Object System.Drawing.Size(450, 20)
+$progressBar.Size = New-Object System.Drawing.Size(785, 20)
 $progressBar.Style = [System.Windows.Forms.ProgressBarStyle]::Continuous
 $form.Controls.Add($progressBar)
 
-# Show the form
-$form.Show()
-$form.Activate()
-$form.Refresh()
+# Finished tasks; selecting one previews its text output on the right, double-clicking opens its files
+$taskList = New-Object System.Windows.Forms.ListBox
+$taskList.Location = New-Object System.Drawing.Point(10, 85)
+$taskList.Size = New-Object System.Drawing.Size(250, 325)
+$form.Controls.Add($taskList)
+$taskListNames = [System.Collections.Generic.List[string]]::new()
+$outputPreview = New-Object System.Windows.Forms.TextBox
+$outputPreview.Location = New-Object System.Drawing.Point(270, 85)
+$outputPreview.Size = New-Object System.Drawing.Size(525, 325)
+$outputPreview.Multiline = $true
+$outputPreview.ReadOnly = $true
+$outputPreview.WordWrap = $false
+$outputPreview.ScrollBars = "Both"
+$outputPreview.Font = New-Object System.Drawing.Font("Consolas", 9)
+$form.Controls.Add($outputPreview)
+# Every task that can have written files; -Resume later drops the ones the interrupted run completed from $diagnosticTasks
+$producingTasks = $diagnosticTasks + $adminTasks + $arm64Tasks
+$taskProducers = Get-WFTaskProducers $producingTasks
+function Get-WFTaskFiles {
+    param([string]$TaskName)
+    Get-ChildItem -Path $filePath -Recurse -File -ErrorAction SilentlyContinue | Where-Object { $taskProducers[$_.BaseName] -eq $TaskName -or $taskProducers[$_.Directory.Name] -eq $TaskName }
+}
+# Errors of failed tasks by task name, shown above the output preview
+$taskErrors = @{}
+function Add-WFTaskListEntry {
+    param($Result, $Job)
+    if ($Job -and $Result.State -ne 'Completed') {
+        $taskErrors[$Result.Task] = @($Job.ChildJobs[0].Error | ForEach-Object { "$_" }) + @($Job.ChildJobs[0].JobStateInfo.Reason.Message | Where-Object { $_ })
+    }
+    $taskListNames.Add($Result.Task)
+    [void]$taskList.Items.Add("$($Result.Task) - $(if ($Result.State -eq 'Completed') { $Result.Severity } else { $Result.State })")
+}
+$taskList.Add_SelectedIndexChanged({
+    if ($taskList.SelectedIndex -lt 0) { return }
+    $taskName = $taskListNames[$taskList.SelectedIndex]
+    $preview = @(if ($taskErrors[$taskName]) { "== $(Get-WFText Errors)"; $taskErrors[$taskName]; "" }) + @(foreach ($file in Get-WFTaskFiles $taskName) {
+        "== $($file.Name)"
+        if ($file.Extension -in ".txt", ".csv", ".json", ".log", ".xml", ".ndjson") { Get-Content -Path $file.FullName -TotalCount 500 }
+        else { Get-WFText BinaryFile ([math]::Round($file.Length / 1KB)) }
+        ""
+    })
+    $outputPreview.Text = if ($preview) { $preview -join "`r`n" } else { Get-WFText NoFiles }
+})
+$taskList.Add_DoubleClick({
+    if ($taskList.SelectedIndex -lt 0) { return }
+    $files = @(Get-WFTaskFiles $taskListNames[$taskList.SelectedIndex])
+    if ($files.Count -eq 1) { Invoke-Item -Path $files[0].FullName }
+    elseif ($files) { Start-Process explorer.exe -ArgumentList "/select,`"$($files[0].FullName)`"" }
+})
+
+# Show the form; with -Quiet or -Console it stays hidden and only its progress state is updated
+if (!$Quiet -and !$Console -and !$offlineMode) {
+    $form.Show()
+    $form.Activate()
+    $form.Refresh()
+}
 
 # Start the background work
 #$job = Start-Job -ScriptBlock $backgroundWork -ArgumentList $form, $progressBar, $label, $filePath, $zipFilePath
 # Run tasks with progress bar
 
+# Cancelling: the job processes and everything they started (dxdiag, dism, defrag, powercfg, ...) are stopped,
+# deepest first. This has to happen before Stop-Job, which would orphan the tools and leave them running.
+function Stop-WFTaskProcesses {
+    $processes = @(Get-CimInstance -ClassName Win32_Process -Property ProcessId, ParentProcessId, Name)
+    $descendants = [System.Collections.Generic.List[int]]::new()
+    $parents = @($PID)
+    while ($parents) {
+        # The console host of this window is a child too and must survive
+        $parents = @($processes | Where-Object { $parents -contains $_.ParentProcessId -and !($_.ParentProcessId -eq $PID -and $_.Name -eq "conhost.exe") -and !$descendants.Contains([int]$_.ProcessId) } | ForEach-Object { [int]$_.ProcessId })
+        $descendants.AddRange([int[]]$parents)
+    }
+    $descendants.Reverse()
+    foreach ($id in $descendants) { Stop-Process -Id $id -Force -ErrorAction SilentlyContinue }
+    if ($descendants.Count) { Write-ToolLog "Stopped $($descendants.Count) task processes" "WARN" }
+}
 # Handle the FormClosing event
 $form.Add_FormClosing({
     param($sender, $e)
+    # Once collection is done the window only shows results and can close freely
+    if ($script:collectionFinished) { return }
     try {
-        $label.Text = "Cancelling all tasks and closing..."
+        $label.Text = Get-WFText Cancelling
+        Stop-WFTaskProcesses
         $jobs | Stop-Job -ErrorAction SilentlyContinue | Remove-Job -Force -ErrorAction SilentlyContinue
-        Start-Job -ScriptBlock { Get-Process -Name dxdiag -ErrorAction SilentlyContinue | Stop-Process -Force -ErrorAction SilentlyContinue }
         $script:stopScript = $true
     #    $form.Close()
     } catch {
-        Write-Host "Error stopping jobs or msinfo32 process: $_" }
+        Write-ToolLog "Error stopping jobs or msinfo32 process: $_" "ERROR" }
 })
 
+# -Console progress: an overall bar with the elapsed time and a child bar with the running tasks.
+# When the output is redirected (log files, RMM agents) a "Progress: n%" line is written on each change instead.
+$lastConsolePercent = -1
+function Write-WFConsoleProgress {
+    param([int]$Percent, [int]$Completed, [int]$Total, [string[]]$RunningTasks, [int]$SecondsRemaining = -1)
+    if ([Console]::IsOutputRedirected -or [Console]::IsErrorRedirected) {
+        if ($Percent -ne $script:lastConsolePercent) { [Console]::Error.WriteLine("Progress: $Percent%") }
+        $script:lastConsolePercent = $Percent
+        return
+    }
+    $elapsed = (Get-Date) - $collectionStarted
+    Write-Progress -Id 1 -Activity "WindowsForum.com Diagnostic Tool" -Status ("{0}% - completed {1} of {2} tasks - {3:mm\:ss} elapsed" -f $Percent, $Completed, $Total, $elapsed) -PercentComplete $Percent -SecondsRemaining $SecondsRemaining
+    if ($RunningTasks) { Write-Progress -Id 2 -ParentId 1 -Activity "Running" -Status ($RunningTasks -join ', ') }
+    else { Write-Progress -Id 2 -ParentId 1 -Activity "Running" -Completed }
+}
+
+# -ProgressFormat json: one JSON object per line for wrapper tooling (RMM agents, installers).
+# Events: "progress" while tasks run, "stage" for the steps after collection, "complete" at the end.
+$progressWriter = $null
+function Write-WFProgressEvent {
+    param([string]$Type, [hashtable]$Data)
+    if ($ProgressFormat -ne "json") { return }
+    if (!$script:progressWriter) {
+        $script:progressWriter = [Console]::Error
+        if ($ProgressPipe) {
+            try {
+                $pipe = New-Object System.IO.Pipes.NamedPipeClientStream(".", ($ProgressPipe -replace '^\\\\\.\\pipe\\', ''), [System.IO.Pipes.PipeDirection]::Out)
+                $pipe.Connect(5000)
+                $script:progressWriter = New-Object System.IO.StreamWriter($pipe)
+            } catch {
+                Write-ToolLog "Cannot connect to progress pipe ${ProgressPipe}, using stderr: $_" "WARN"
+            }
+        }
+    }
+    $record = [ordered]@{ type = $Type; time = (Get-Date).ToString('o') }
+    foreach ($key in $Data.Keys) { $record[$key] = $Data[$key] }
+    $script:progressWriter.WriteLine(($record | ConvertTo-Json -Depth 4 -Compress))
+    $script:progressWriter.Flush()
+}
+
+# Runs one diagnostic task inside its job and reports what it cost. The job process is put in a
+# Windows job object so CPU, memory and disk writes of child tools (dxdiag, dism, ...) are counted too.
+$taskRunner = {
+    param($taskScript, $filePath, $zipFilePath, $taskOptions)
+    # Collectors call Add-TaskFinding to flag what matters in their output; each finding is returned
+    # through the job's output stream. Don't call it inside a pipeline that ends in Out-File.
+    function Add-TaskFinding {
+        param([ValidateSet("Info", "Warning", "Critical")][string]$Severity, [string]$Message, [string]$Recommendation, [string]$Evidence)
+        [PSCustomObject]@{ WFFinding = $true; Severity = $Severity; Message = $Message; Recommendation = $Recommendation; Evidence = $Evidence }
+    }
+    # Helper for list-shaped tasks: the usual text file, plus a CSV copy when -Csv was given
+    function Out-TaskTable {
+        param([string]$Path, [string[]]$CsvProperty = "*", [Parameter(ValueFromPipeline = $true)]$InputObject)
+        begin { $rows = New-Object System.Collections.ArrayList }
+        process { [void]$rows.Add($InputObject) }
+        end {
+            $rows | Out-File "$Path.txt"
+            if ($taskOptions.Csv) { $rows | Select-Object -Property $CsvProperty | Export-Csv -Path "$Path.csv" -NoTypeInformation -Encoding UTF8 }
+        }
+    }
+    # Helper for WMI tasks: human-readable text plus clean JSON (ISO 8601 dates, real arrays and nulls)
+    function Export-CimClass {
+        param([string]$ClassName, [string]$Path)
+        $instances = Get-CimInstance -ClassName $ClassName
+        $instances | Out-File "$Path.txt"
+        $records = @($instances | ForEach-Object {
+            $record = [ordered]@{}
+            foreach ($property in $_.CimInstanceProperties) {
+                $value = $property.Value
+                if ($value -is [datetime]) { $value = $value.ToString('o') }
+                $record[$property.Name] = $value
+            }
+            $record
+        })
+        ConvertTo-Json -InputObject $records -Depth 3 | Out-File "$Path.json"
+    }
+    $accountingJob = [IntPtr]::Zero
+    try {
+        Add-Type -TypeDefinition @"
+using System;
+using System.Runtime.InteropServices;
+public static class WFJobAccounting {
+    [StructLayout(LayoutKind.Sequential)] public struct BASIC_AND_IO_ACCOUNTING { public long TotalUserTime; public long TotalKernelTime; public long ThisPeriodTotalUserTime; public long ThisPeriodTotalKernelTime; public uint TotalPageFaultCount; public uint TotalProcesses; public uint ActiveProcesses; public uint TotalTerminatedProcesses; public ulong ReadOperationCount; public ulong WriteOperationCount; public ulong OtherOperationCount; public ulong ReadTransferCount; public ulong WriteTransferCount; public ulong OtherTransferCount; }
+    [StructLayout(LayoutKind.Sequential)] public struct BASIC_LIMIT { public long PerProcessUserTimeLimit; public long PerJobUserTimeLimit; public uint LimitFlags; public UIntPtr MinimumWorkingSetSize; public UIntPtr MaximumWorkingSetSize; public uint ActiveProcessLimit; public UIntPtr Affinity; public uint PriorityClass; public uint SchedulingClass; }
+    [StructLayout(LayoutKind.Sequential)] public struct EXTENDED_LIMIT { public BASIC_LIMIT BasicLimitInformation; public ulong ReadOperationCount; public ulong WriteOperationCount; public ulong OtherOperationCount; public ulong ReadTransferCount; public ulong WriteTransferCount; public ulong OtherTransferCount; public UIntPtr ProcessMemoryLimit; public UIntPtr JobMemoryLimit; public UIntPtr PeakProcessMemoryUsed; public UIntPtr PeakJobMemoryUsed; }
+    [DllImport("kernel32.dll")] public static extern IntPtr CreateJobObject(IntPtr attributes, string name);
+    [DllImport("kernel32.dll")] public static extern bool AssignProcessToJobObject(IntPtr job, IntPtr process);
+    [DllImport("kernel32.dll")] public static extern IntPtr GetCurrentProcess();
+    [DllImport("kernel32.dll")] public static extern bool QueryInformationJobObject(IntPtr job, int infoClass, out BASIC_AND_IO_ACCOUNTING info, int size, IntPtr returned);
+    [DllImport("kernel32.dll")] public static extern bool QueryInformationJobObject(IntPtr job, int infoClass, out EXTENDED_LIMIT info, int size, IntPtr returned);
+}
+"@
+        $accountingJob = [WFJobAccounting]::CreateJobObject([IntPtr]::Zero, $null)
+        if (![WFJobAccounting]::AssignProcessToJobObject($accountingJob, [WFJobAccounting]::GetCurrentProcess())) { $accountingJob = [IntPtr]::Zero }
+    } catch { }
+    try {
+        & ([scriptblock]::Create($taskScript)) $filePath $zipFilePath
+    } finally {
+        if ($accountingJob -ne [IntPtr]::Zero) {
+            $usage = New-Object WFJobAccounting+BASIC_AND_IO_ACCOUNTING
+            $limits = New-Object WFJobAccounting+EXTENDED_LIMIT
+            [void][WFJobAccounting]::QueryInformationJobObject($accountingJob, 8, [ref]$usage, [System.Runtime.InteropServices.Marshal]::SizeOf($usage), [IntPtr]::Zero) # JobObjectBasicAndIoAccountingInformation
+            [void][WFJobAccounting]::QueryInformationJobObject($accountingJob, 9, [ref]$limits, [System.Runtime.InteropServices.Marshal]::SizeOf($limits), [IntPtr]::Zero) # JobObjectExtendedLimitInformation
+            [PSCustomObject]@{
+                WFTaskAccounting = $true
+                CpuSeconds       = [math]::Round(($usage.TotalUserTime + $usage.TotalKernelTime) / 1e7, 1)
+                PeakMemoryMB     = [math]::Round($limits.PeakJobMemoryUsed.ToUInt64() / 1MB, 1)
+                DiskWrittenMB    = [math]::Round($usage.WriteTransferCount / 1MB, 1)
+                Processes        = $usage.TotalProcesses
+            }
+        }
+    }
+}
+
+# Builds the result record of a finished task job: state, severity, findings and resource usage
+$severityOrder = @{ Critical = 0; Warning = 1; Info = 2 }
+function Get-WFTaskResult {
+    param($Job, [string]$TaskName)
+    $duration = if ($Job.PSEndTime) { [math]::Round(($Job.PSEndTime - $Job.PSBeginTime).TotalSeconds, 1) } else { "?" }
+    $output = Receive-Job -Job $Job -Keep -ErrorAction SilentlyContinue
+    $accounting = $output | Where-Object { $_.WFTaskAccounting } | Select-Object -Last 1
+    $taskFindings = @($output | Where-Object { $_.WFFinding } | ForEach-Object {
+        New-Finding $_.Severity $_.Message $_.Recommendation $_.Evidence | Add-Member -NotePropertyName Rule -NotePropertyValue $TaskName -PassThru
+    })
+    if ($Job.State -ne 'Completed') {
+        $taskFindings += New-Finding "Warning" "Task $TaskName did not complete ($($Job.State))." "See tool-logs\wfdiag.log for the error." "tool-logs\wfdiag.log" |
+            Add-Member -NotePropertyName Rule -NotePropertyValue $TaskName -PassThru
+    }
+    # A task is as severe as its worst finding
+    $taskSeverity = $taskFindings | Sort-Object { $severityOrder[$_.Severity] } | Select-Object -First 1 -ExpandProperty Severity
+    [PSCustomObject]@{
+        Task          = $TaskName
+        State         = "$($Job.State)"
+        Severity      = if ($taskSeverity) { $taskSeverity } else { "Info" }
+        Findings      = $taskFindings
+        Started       = $Job.PSBeginTime
+        WallSeconds   = $duration
+        CpuSeconds    = $accounting.CpuSeconds
+        PeakMemoryMB  = $accounting.PeakMemoryMB
+        DiskWrittenMB = $accounting.DiskWrittenMB
+        Processes     = $accounting.Processes
+    }
+}
+
+# -Ndjson: one JSON line per task as soon as it finishes, for tools that process results incrementally
+$ndjsonWritten = @{}
+# Journal of completed tasks (tool-logs\journal.ndjson), written as each task finishes so -Resume can
+# reuse the results and files of a run that was interrupted by a crash or reboot
+$journaledTasks = @{}
+function Add-WFJournalEntry {
+    param($Result)
+    if ($DryRun -or $Result.State -ne 'Completed' -or $journaledTasks.ContainsKey($Result.Task)) { return }
+    $journaledTasks[$Result.Task] = $true
+    $entry = $Result | Select-Object -Property * -ExcludeProperty Started
+    $entry | Add-Member -NotePropertyName Started -NotePropertyValue $(if ($Result.Started) { $Result.Started.ToString('o') })
+    Add-Content -Path $journalFile -Value ($entry | ConvertTo-Json -Depth 4 -Compress) -Encoding UTF8
+}
+function Read-WFJournal {
+    Get-Content -Path $journalFile -Encoding UTF8 | Where-Object { $_ } | ForEach-Object {
+        $entry = $_ | ConvertFrom-Json
+        if ($entry.Started) { $entry.Started = [datetime]$entry.Started }
+        $entry.Findings = @($entry.Findings)
+        $journaledTasks[$entry.Task] = $true
+        $entry
+    }
+}
+function Write-WFTaskRecord {
+    param($Result)
+    if (!$Ndjson -or $ndjsonWritten.ContainsKey($Result.Task)) { return }
+    $ndjsonWritten[$Result.Task] = $true
+    $record = $Result | Select-Object -Property * -ExcludeProperty Started
+    $record | Add-Member -NotePropertyName Started -NotePropertyValue $(if ($Result.Started) { $Result.Started.ToString('o') })
+    $line = $record | ConvertTo-Json -Depth 4 -Compress
+    if ($Ndjson -eq "-") { [Console]::Out.WriteLine($line) }
+    else { Add-Content -Path $Ndjson -Value $line -Encoding UTF8 }
+}
+
+# Run options the tasks can read through $taskOptions
+$taskOptions = @{ Csv = [bool]($Csv -or $Sql); MonitorMinutes = $MonitorMinutes; MonitorInterval = [math]::Max(1, $MonitorInterval) }
+
+# -Analyze: runs the minidump and event log analyzers and the findings rules on a bundle someone else collected
+# and writes the results and a summary to <bundle>-Analysis next to it; findings recorded in the bundle are included
+if ($Analyze) {
+    $source = (Resolve-Path -Path $Analyze).Path
+    $analysisPath = "$($source -replace '\.zip$', '')-Analysis"
+    if (Test-Path -Path $analysisPath) { Remove-Item -Path $analysisPath -Recurse -Force }
+    New-Item -ItemType Directory -Path $analysisPath -Force | Out-Null
+    if (!(Get-Item -Path $source).PSIsContainer) {
+        [System.IO.Compression.ZipFile]::ExtractToDirectory($source, "$analysisPath\bundle")
+        $source = "$analysisPath\bundle"
+    }
+    # Analyze tells the tasks to read only the bundle, even where it has no files for them
+    $taskOptions.Analyze = $true
+    $taskOptions.MinidumpPath = if (Test-Path -Path "$source\Minidump") { "$source\Minidump" } else { $source }
+    $taskOptions.EventLogPaths = @(Get-ChildItem -Path $source -Filter "*.evtx" -Recurse | ForEach-Object FullName)
+    # Elevated, $diagnosticTasks already holds the admin tasks, so each analyzer is picked once by name
+    $analyzers = @("Minidump Summary", "Event Log Summary" | ForEach-Object { $name = $_; $adminTasks + $diagnosticTasks | Where-Object { $_.Name -eq $name } | Select-Object -First 1 })
+    $analyzerJobs = @($analyzers | ForEach-Object { Start-Job -ScriptBlock $taskRunner -ArgumentList $_.Task.ToString(), $analysisPath, $null, $taskOptions })
+    $analyzerJobs | Wait-Job | Out-Null
+    $results = @(for ($i = 0; $i -lt $analyzers.Count; $i++) { Get-WFTaskResult $analyzerJobs[$i] $analyzers[$i].Name })
+    $analysisFindings = @($results | ForEach-Object { $_.Findings })
+    # The findings rules read only the bundle's files, so they run here as they did after the collection
+    $bundleFolder = Get-ChildItem -Path $source -Filter "WindowsForum-Snapshot.json" -Recurse | Select-Object -First 1 -ExpandProperty DirectoryName
+    $analysisFindings += @(Invoke-WFFindingRules $(if ($bundleFolder) { $bundleFolder } else { $source }))
+    # Findings the collecting machine recorded (bundles from 2.0.6 on carry WindowsForum-Report.json); those of
+    # the rules were just made again, from the same files
+    $bundleReport = Get-ChildItem -Path $source -Filter "WindowsForum-Report.json" -Recurse | Select-Object -First 1
+    if ($bundleReport) {
+        $report = Get-Content -Path $bundleReport.FullName -Raw | ConvertFrom-Json
+        $analysisFindings += @($report.Findings | Where-Object { $findingRules.Name -notcontains $_.Source } |
+            ForEach-Object { New-Finding $_.Severity $_.Message $_.Recommendation $_.Evidence | Add-Member -NotePropertyName Rule -NotePropertyValue $_.Source -PassThru })
+    }
+    $analysisFindings = @($analysisFindings | Sort-Object { $severityOrder[$_.Severity] })
+    $summary = @(
+        "Analysis of $Analyze"
+        if ($report) { "Collected on $($report.System.Manufacturer) $($report.System.Model), $($report.System.OS) (build $($report.System.Build)) at $($report.Collected)" }
+        "Minidumps: $(@(Get-ChildItem -Path $taskOptions.MinidumpPath -Filter '*.dmp' -ErrorAction SilentlyContinue).Count), exported event logs: $($taskOptions.EventLogPaths.Count)"
+        ""
+        if ($analysisFindings) { $analysisFindings | ForEach-Object { "[$($_.Severity)] $($_.Message)"; if ($_.Recommendation) { "    $($_.Recommendation)" } } } else { "No findings." }
+        ""
+        "Details: $analysisPath"
+    )
+    $summary | Out-File "$analysisPath\WindowsForum-Summary.txt"
+    $analyzerJobs | Remove-Job -Force
+    $form.Dispose()
+    $summary
+    exit
+}
+
+# A file given to -Ndjson starts empty, so it holds only the records of this run
+if ($Ndjson -and $Ndjson -ne "-") { New-Item -ItemType File -Path $Ndjson -Force | Out-Null }
+# -Resume: reuse what the interrupted run completed and only run the rest
+$resumedResults = @()
+if ($Resume) {
+    $resumedResults = @(Read-WFJournal)
+    $diagnosticTasks = @($diagnosticTasks | Where-Object { !$journaledTasks.ContainsKey($_.Name) })
+    Write-ToolLog "Resuming: $($resumedResults.Count) tasks completed earlier, $($diagnosticTasks.Count) to run"
+    $resumedResults | ForEach-Object { Add-WFTaskListEntry $_; Write-WFTaskRecord $_ }
+}
+$finishedJobs = @{}
 $totalTasks = $diagnosticTasks.Count
 $currentTask = 0
 $jobs = @()
-$currentTaskIndex = 0
-foreach ($task in $diagnosticTasks) {
-    $jobs += Start-Job -ScriptBlock $task.Task -ArgumentList $filePath, $zipFilePath
-    $progressBar.Value = ($currentTaskIndex / $totalTasks) * 100
-    $label.Text = "Exporting Logs... $($diagnosticTasks[$currentTaskIndex].Name) ($currentTaskIndex of $totalTasks)"
-    [System.Windows.Forms.Application]::DoEvents()
-    $currentTaskIndex++
+# Tasks start in list order while fewer than -MaxJobs are running; $jobs[$i] always belongs to $diagnosticTasks[$i].
+# Dot-sourced so it updates $jobs in the script scope. The default limit leaves tasks waiting, so Pause has
+# something to hold back; once every task has started there is nothing left to pause.
+$maxJobs = if ($MaxJobs -gt 0) { $MaxJobs } else { [math]::Max(4, [Environment]::ProcessorCount) }
+$startPendingTasks = {
+    while (!$script:paused -and $jobs.Count -lt $totalTasks -and @($jobs | Where-Object { $_.State -eq 'Running' }).Count -lt $maxJobs) {
+        $task = $diagnosticTasks[$jobs.Count]
+        $jobs += Start-Job -ScriptBlock $taskRunner -ArgumentList $task.Task.ToString(), $filePath, $zipFilePath, $taskOptions
+        Write-ToolLog "Started task $($task.Name)"
+        $label.Text = Get-WFText Starting $task.Name $jobs.Count $totalTasks
+        [System.Windows.Forms.Application]::DoEvents()
+    }
+    if ($jobs.Count -eq $totalTasks) { $pauseButton.Enabled = $false }
 }
+. $startPendingTasks
 
-while (($jobs.State -contains 'Running') -and !$script:stopScript) {
-    if ($form.IsDisposed -or $script:stopScript) {
-        $jobs | ForEach-Object { Stop-Job -Id $_.Id -ErrorAction SilentlyContinue}
-        break
+$readConsoleKeys = $Console -and ![Console]::IsInputRedirected
+if ($readConsoleKeys) { [Console]::TreatControlCAsInput = $true }
+while ((($jobs.State -contains 'Running') -or $jobs.Count -lt $totalTasks) -and !$script:stopScript -and !$script:stopRequested) {
+    . $startPendingTasks
+    # Ctrl+C in the terminal cancels the same way as closing the window
+    while ($readConsoleKeys -and [Console]::KeyAvailable) {
+        $key = [Console]::ReadKey($true)
+        if ($key.Key -eq "C" -and $key.Modifiers -band [ConsoleModifiers]::Control) { $script:stopScript = $true }
+        elseif ($key.Key -eq "P" -and $pauseButton.Enabled) { Switch-WFPause }
     }
+    if ($form.IsDisposed -or $script:stopScript -or $script:stopRequested) { break }
     $runningJobs = $jobs | Where-Object { $_.State -eq 'Running' }
-    $runningTasks = $runningJobs | ForEach-Object { $diagnosticTasks[$jobs.IndexOf($_)].Name }
-    $remainingTasks = $runningJobs.Count
-    $completedTasks = $totalTasks - $remainingTasks
-    $progressBar.Value = ($completedTasks / $totalTasks) * 100
-    $label.Text = "Running Diagnostics... This will take awhile... completed $completedTasks of $totalTasks. $($runningTasks -join ', ')"
+    for ($i = 0; $i -lt $jobs.Count; $i++) {
+        if ($jobs[$i].State -ne 'Running' -and !$finishedJobs.ContainsKey($jobs[$i].Id)) {
+            $finishedJobs[$jobs[$i].Id] = $true
+            $result = Get-WFTaskResult $jobs[$i] $diagnosticTasks[$i].Name
+            Write-WFTaskRecord $result
+            Add-WFJournalEntry $result
+            if ($Interactive) { "{0,-9} {1,-8} {2} ({3} s)" -f $result.State, $result.Severity, $result.Task, $result.WallSeconds }
+            Add-WFTaskListEntry $result $jobs[$i]
+        }
+    }
+    $partialProgress = 0
+    $runningTasks = $runningJobs | ForEach-Object {
+        $name = $diagnosticTasks[$jobs.IndexOf($_)].Name
+        # Tasks wrapping long tools report sub-task progress through Write-Progress
+        $progress = $_.ChildJobs[0].Progress | Select-Object -Last 1
+        $expected = Get-WFExpectedSeconds $name
+        if ($progress -and $progress.PercentComplete -ge 0) {
+            $partialProgress += $progress.PercentComplete / 100
+            "$name ($($progress.PercentComplete)%)"
+        } elseif ($expected) {
+            "$name ($([math]::Round(((Get-Date) - $_.PSBeginTime).TotalSeconds)) of ~$expected s)"
+        } else { $name }
+    }
+    # Time left: the longest running task, or all remaining work spread over the -MaxJobs slots if that takes longer.
+    # Tasks without history count as 30 s; there is no estimate at all before the first collection.
+    $secondsLeft = -1
+    if ($durationHistory.Count) {
+        $runningLeft = @($runningJobs | ForEach-Object {
+            $expected = Get-WFExpectedSeconds $diagnosticTasks[$jobs.IndexOf($_)].Name
+            [math]::Max(0, $(if ($expected) { $expected } else { 30 }) - ((Get-Date) - $_.PSBeginTime).TotalSeconds)
+        })
+        $pendingExpected = @($diagnosticTasks | Select-Object -Skip $jobs.Count | ForEach-Object { $expected = Get-WFExpectedSeconds $_.Name; if ($expected) { $expected } else { 30 } })
+        $secondsLeft = [int][math]::Max([double]($runningLeft | Measure-Object -Maximum).Maximum, (($runningLeft + $pendingExpected) | Measure-Object -Sum).Sum / $maxJobs)
+    }
+    $completedTasks = @($jobs | Where-Object { $_.State -ne 'Running' }).Count
+    $progressBar.Value = [math]::Min(100, (($completedTasks + $partialProgress) / $totalTasks) * 100)
+    $label.Text = "$(if ($script:paused) { "$(Get-WFText Paused) " })$(Get-WFText Running $completedTasks $totalTasks ($runningTasks -join ', '))$(if ($secondsLeft -ge 0) { " $(Get-WFText TimeLeft ([timespan]::FromSeconds($secondsLeft)))" })"
+    if ($Console) { Write-WFConsoleProgress $progressBar.Value $completedTasks $totalTasks $runningTasks $secondsLeft }
+    Write-WFProgressEvent "progress" @{ percent = $progressBar.Value; completed = $completedTasks; total = $totalTasks; secondsLeft = $(if ($secondsLeft -ge 0) { $secondsLeft }); running = @($runningJobs | ForEach-Object {
+        [ordered]@{ task = $diagnosticTasks[$jobs.IndexOf($_)].Name; seconds = [math]::Round(((Get-Date) - $_.PSBeginTime).TotalSeconds); expectedSeconds = Get-WFExpectedSeconds $diagnosticTasks[$jobs.IndexOf($_)].Name }
+    }) }
     [System.Windows.Forms.Application]::DoEvents()
     Start-Sleep -Seconds 1
 }
+if ($readConsoleKeys) { [Console]::TreatControlCAsInput = $false }
 # Check if the script was stopped
 if ($script:stopScript) {
-    $label.Text = "Stopped. Cancelling all tasks and closing..."
+    Write-ToolLog "Collection cancelled by user" "WARN"
+    $label.Text = Get-WFText StoppedClosing
     $form.Refresh()
-    # Stop all running jobs asynchronously
+    Stop-WFTaskProcesses
     $jobs | Where-Object { $_.State -eq 'Running' } | Stop-Job -PassThru | Receive-Job -Wait -AutoRemoveJob
     Get-Job | Remove-Job -Force
-    Start-Job -ScriptBlock { Get-Process -Name dxdiag -ErrorAction SilentlyContinue | Stop-Process -Force -ErrorAction SilentlyContinue }
+    if ($Console) { "Collection cancelled. Run again with -Resume to keep the tasks that finished." }
     $form.Close()
     exit
 }
 else {
+$stopButton.Enabled = $false
+$pauseButton.Enabled = $false
+$stopNote = $null
+if ($script:stopRequested) {
+    Write-ToolLog "Collection stopped by user, packaging the finished tasks" "WARN"
+    Stop-WFTaskProcesses
+    $jobs | Where-Object { $_.State -eq 'Running' } | Stop-Job
+    $skippedTasks += $diagnosticTasks | Select-Object -Skip $jobs.Count | ForEach-Object { [PSCustomObject]@{ Task = $_.Name; Reason = "Stopped by user" } }
+    $diagnosticTasks = @($diagnosticTasks | Select-Object -First $jobs.Count)
+    $stopNote = Get-WFText StopNote
+}
 # Wait for all jobs to complete
-$jobs | Wait-Job
+$jobs | Wait-Job | Out-Null
+$taskResults = @($resumedResults)
+for ($i = 0; $i -lt $jobs.Count; $i++) {
+    $job = $jobs[$i]
+    $result = Get-WFTaskResult $job $diagnosticTasks[$i].Name
+    Write-WFTaskRecord $result
+    Add-WFJournalEntry $result
+    if (!$finishedJobs.ContainsKey($job.Id)) { Add-WFTaskListEntry $result $job }
+    $taskResults += $result
+    $duration = $result.WallSeconds
+    Write-ToolLog "Task $($diagnosticTasks[$i].Name) finished: $($job.State) in $duration s" $(if ($job.State -eq 'Completed') { "INFO" } else { "ERROR" })
+    foreach ($err in $job.ChildJobs[0].Error) {
+        Write-ToolLog "Task $($diagnosticTasks[$i].Name): $err" "ERROR"
+    }
+    if ($job.ChildJobs[0].JobStateInfo.Reason) {
+        Write-ToolLog "Task $($diagnosticTasks[$i].Name): $($job.ChildJobs[0].JobStateInfo.Reason.Message)" "ERROR"
+    }
+}
+# Appendix: what each collector cost, including the external tools it started
+$taskResults | Sort-Object { $_.WallSeconds -as [double] } -Descending | Format-Table Task, State, WallSeconds, CpuSeconds, PeakMemoryMB, DiskWrittenMB, Processes -AutoSize | Out-File "$filePath\WindowsForum-TaskResources.txt"
 Get-Job | Remove-Job -Force
+# Durations of this run's completed tasks feed the estimates of the next runs
+try {
+    foreach ($result in @($taskResults | Select-Object -Skip $resumedResults.Count | Where-Object { $_.State -eq 'Completed' -and $_.WallSeconds -is [double] })) {
+        $durationHistory[$result.Task] = @(@($durationHistory[$result.Task]) + $result.WallSeconds | Where-Object { $null -ne $_ } | Select-Object -Last 5)
+    }
+    New-Item -ItemType Directory -Path (Split-Path -Path $durationHistoryPath) -Force | Out-Null
+    $durationHistory | ConvertTo-Json -Depth 3 | Out-File $durationHistoryPath -Encoding utf8
+} catch {
+    Write-ToolLog "Could not save the task durations: $_" "WARN"
+}
+# Drift since the saved baseline, so regressions after updates stand out
+$snapshotFile = "$filePath\WindowsForum-Snapshot.json"
+if ((Test-Path -Path $baselinePath) -and (Test-Path -Path $snapshotFile)) {
+    try {
+        $baseline = Get-Content -Path $baselinePath -Raw | ConvertFrom-Json
+        $drift = @(Compare-WFSnapshot $baseline (Get-Content -Path $snapshotFile -Raw | ConvertFrom-Json))
+        & {
+            "Changes since the baseline saved on $($baseline.Collected):"
+            if ($drift) { $drift | Format-Table -AutoSize -Wrap | Out-String -Width 200 } else { "No changes." }
+        } | Out-File "$filePath\WindowsForum-Drift.txt"
+        Write-ToolLog "Compared against baseline from $($baseline.Collected): $($drift.Count) changes"
+    } catch {
+        Write-ToolLog "Baseline comparison failed: $_" "ERROR"
+    }
+}
+if ($SaveBaseline -and (Test-Path -Path $snapshotFile)) {
+    New-Item -ItemType Directory -Path (Split-Path -Path $baselinePath) -Force | Out-Null
+    Copy-Item -Path $snapshotFile -Destination $baselinePath -Force
+    Write-ToolLog "Saved baseline to $baselinePath"
+}
+# Run the findings rules against the collected output
+$label.Text = Get-WFText Analyzing
+$form.Refresh()
+Write-WFProgressEvent "stage" @{ stage = "findings" }
+$findings = @($taskResults | ForEach-Object { $_.Findings }) + @(Invoke-WFFindingRules $filePath)
+$findings = @($findings | Sort-Object { $severityOrder[$_.Severity] })
+$narrative = try { @(Get-WFNarrative $filePath $findings) } catch { Write-ToolLog "Narrative generation failed: $_" "ERROR"; @() }
+& {
+    "WindowsForum.com Diagnostic Tool $version - Summary"
+    "Collected $(Get-Date -Format 'yyyy-MM-dd HH:mm') on $env:COMPUTERNAME"
+    ""
+    $narrative
+    ""
+    "Warnings:"
+    if ($findings) { $findings | ForEach-Object { "  [$($_.Severity)] $($_.Message)" } } else { "  None" }
+    ""
+    "Recommendations:"
+    if ($findings) { $findings | ForEach-Object { $_.Recommendation } | Select-Object -Unique | ForEach-Object { "  - $_" } } else { "  None" }
+    ""
+    "Tasks needing attention:"
+    $flaggedTasks = $taskResults | Where-Object { $_.Severity -ne 'Info' } | Sort-Object { $severityOrder[$_.Severity] }
+    if ($flaggedTasks) { $flaggedTasks | ForEach-Object { "  [$($_.Severity)] $($_.Task) ($(@($_.Findings).Count) finding(s))" } } else { "  None" }
+} | Out-File "$filePath\WindowsForum-Summary.txt"
+try {
+    New-WFForumPost $filePath $narrative $findings "Markdown" | Out-File "$filePath\WindowsForum-ForumPost.md" -Encoding utf8
+    New-WFForumPost $filePath $narrative $findings "BBCode" | Out-File "$filePath\WindowsForum-ForumPost-BBCode.txt" -Encoding utf8
+} catch {
+    Write-ToolLog "Forum post generation failed: $_" "ERROR"
+}
+if ($Sarif) {
+    try {
+        Export-WFSarif $findings | Out-File "$filePath\WindowsForum-Findings.sarif" -Encoding utf8
+    } catch {
+        Write-ToolLog "SARIF export failed: $_" "ERROR"
+    }
+}
+if ($Sql) {
+    try {
+        Export-WFSql $filePath $findings $taskResults | Out-File "$filePath\WindowsForum-Results.sql" -Encoding utf8
+    } catch {
+        Write-ToolLog "SQL export failed: $_" "ERROR"
+    }
+}
+try {
+    New-WFJsonReport $filePath $narrative $findings $taskResults | Out-File "$filePath\WindowsForum-Report.json" -Encoding utf8
+} catch {
+    Write-ToolLog "JSON report generation failed: $_" "ERROR"
+}
+try {
+    New-WFCollectionInfo $taskResults $skippedTasks $PSBoundParameters $collectionStarted | Out-File "$filePath\collection-info.json" -Encoding utf8
+} catch {
+    Write-ToolLog "Collection info generation failed: $_" "ERROR"
+}
+try {
+    New-WFHtmlReport $filePath $narrative $findings $taskResults | Out-File "$filePath\WindowsForum-Report.html" -Encoding utf8
+} catch {
+    Write-ToolLog "HTML report generation failed: $_" "ERROR"
+}
+if ($Redact) {
+    try {
+        Write-ToolLog "Redacting personal identifiers"
+        Invoke-WFRedaction $filePath | Out-File "$filePath\WindowsForum-Redaction.txt"
+    } catch {
+        Write-ToolLog "Redaction failed: $_" "ERROR"
+    }
+}
+Write-ToolLog "Compressing results ($ArchiveFormat, $Compression)"
+Write-WFProgressEvent "stage" @{ stage = "archive" }
+try {
+    New-WFManifest $filePath $producingTasks $collectionStarted | Out-File "$filePath\manifest.json" -Encoding utf8
+} catch {
+    Write-ToolLog "Manifest generation failed: $_" "ERROR"
+}
 # Compress results
-[System.IO.Compression.ZipFile]::CreateFromDirectory($filePath, $zipFilePath)
-$label.Text = "Diagnostics complete. Results have been saved to $zipFilePath. Visit WindowsForum.com!"
+$zipFilePath = New-WFArchive $filePath $archiveBasePath $ArchiveFormat $Compression
+$passwordNote = ""
+$encrypted = $false
+if ($Encrypt -or $Password) {
+    if (!$Password) { $Password = New-WFArchivePassword }
+    # The plain archive is only removed once the encrypted one decrypts back to the same bytes
+    $roundTripPath = "$zipFilePath.check"
+    try {
+        Protect-WFArchive $zipFilePath $Password "$zipFilePath.aes"
+        Unprotect-WFArchive "$zipFilePath.aes" $Password $roundTripPath
+        if ((Get-FileHash -Path $roundTripPath -Algorithm SHA256).Hash -ne (Get-FileHash -Path $zipFilePath -Algorithm SHA256).Hash) { throw "the encrypted archive does not decrypt to the original" }
+        Remove-Item -Path $zipFilePath -Force
+        $zipFilePath = "$zipFilePath.aes"
+        $encrypted = $true
+        $passwordNote = Get-WFText PasswordNote $Password
+    } catch {
+        Write-ToolLog "Encryption failed, keeping the unencrypted archive: $_" "ERROR"
+        Remove-Item -Path "$zipFilePath.aes" -Force -ErrorAction SilentlyContinue
+        $passwordNote = Get-WFText EncryptFailed
+    } finally {
+        Remove-Item -Path $roundTripPath -Force -ErrorAction SilentlyContinue
+    }
+}
+if ($Sign) {
+    try {
+        $keyPath = if ($SigningKey) { $SigningKey } else { Join-Path -Path $env:LOCALAPPDATA -ChildPath "WindowsForum\signing.key" }
+        New-WFSignature $zipFilePath $keyPath
+    } catch {
+        Write-ToolLog "Signing failed: $_" "ERROR"
+    }
+}
+$uploadNote = ""
+if ($UploadUrl) {
+    Write-WFProgressEvent "stage" @{ stage = "upload" }
+    try {
+        Write-ToolLog "Uploading archive to $(([uri]$UploadUrl).Host)"
+        $shareLink = Send-WFArchive $zipFilePath $UploadUrl {
+            param($percent)
+            $label.Text = Get-WFText Uploading $percent
+            $progressBar.Value = $percent
+            [System.Windows.Forms.Application]::DoEvents()
+        }
+        if (Test-Path -Path "$zipFilePath.sig") { [void](Send-WFArchive "$zipFilePath.sig" $UploadUrl) }
+        if ($shareLink) {
+            Set-Clipboard -Value $shareLink
+            $uploadNote = Get-WFText UploadNote $shareLink
+        } else {
+            $uploadNote = Get-WFText UploadedNote
+        }
+        Write-ToolLog "Upload complete"
+    } catch {
+        Write-ToolLog "Upload failed: $_" "ERROR"
+        $uploadNote = Get-WFText UploadFailedNote $_.Exception.Message
+    }
+}
+$splitNote = ""
+if ($SplitSizeMB -gt 0 -and (Get-Item -Path $zipFilePath).Length -gt [int64]$SplitSizeMB * 1MB) {
+    $parts = @(Split-WFArchive $zipFilePath $SplitSizeMB)
+    Write-ToolLog "Split archive into $($parts.Count) parts of $SplitSizeMB MB"
+    $splitNote = Get-WFText SplitNote $parts.Count "$zipFilePath.manifest.txt"
+    $zipFilePath = $parts[0]
+}
+# -KeepArchives/-KeepDays/-KeepTotalMB: older archives whose name fits the -ArchiveName template are removed once
+# they are past the count, the age or the size budget. The newest archive always stays. An archive is grouped with its .sig,
+# its split parts (.001, .002, ...) and their .manifest.txt, which remain after -SplitSizeMB removed the archive
+# itself, and the group is counted and removed as one. -RedactArchive copies (-redacted) are the user's own.
+if ($KeepArchives -gt 0 -or $KeepDays -gt 0 -or $KeepTotalMB -gt 0) {
+    $archives = @(Get-ChildItem -Path $outputFolder -File | Group-Object { $_.Name -replace '\.(\d{3}|manifest\.txt|sig)$', '' } |
+        Where-Object { $_.Name -match $archivePattern -and $_.Name -notmatch '-redacted\.' } |
+        Sort-Object { ($_.Group | Sort-Object LastWriteTime -Descending | Select-Object -First 1).LastWriteTime } -Descending)
+    $totalMB = 0
+    for ($i = 0; $i -lt $archives.Count; $i++) {
+        $related = $archives[$i].Group
+        $newest = ($related | Sort-Object LastWriteTime -Descending | Select-Object -First 1).LastWriteTime
+        $totalMB += ($related | Measure-Object Length -Sum).Sum / 1MB
+        $reason = if ($i -eq 0) { $null }
+            elseif ($KeepArchives -gt 0 -and $i -ge $KeepArchives) { "more than $KeepArchives archives" }
+            elseif ($KeepDays -gt 0 -and $newest -lt (Get-Date).AddDays(-$KeepDays)) { "older than $KeepDays days" }
+            elseif ($KeepTotalMB -gt 0 -and $totalMB -gt $KeepTotalMB) { "over $KeepTotalMB MB in total" }
+        if (!$reason) { continue }
+        Write-ToolLog "Removing old archive $($archives[$i].Name) ($reason): $(($related.Name | Sort-Object) -join ', ')"
+        $related | Remove-Item -Force
+    }
+}
+Write-WFProgressEvent "complete" @{ archive = $zipFilePath; parts = @($parts | Where-Object { $_ }); upload = $shareLink; critical = @($findings | Where-Object { $_.Severity -eq 'Critical' }).Count; warning = @($findings | Where-Object { $_.Severity -eq 'Warning' }).Count }
+if ($Quiet) {
+    [ordered]@{
+        Archive  = $zipFilePath
+        Parts    = @($parts | Where-Object { $_ })
+        Password = if ($encrypted) { $Password } else { $null }
+        Upload   = $shareLink
+        Tasks    = @($taskResults | ForEach-Object { [ordered]@{ Task = $_.Task; State = $_.State; Severity = $_.Severity } })
+        Critical = @($findings | Where-Object { $_.Severity -eq 'Critical' }).Count
+        Warning  = @($findings | Where-Object { $_.Severity -eq 'Warning' }).Count
+    } | ConvertTo-Json -Depth 4 -Compress
+    $form.Dispose()
+    exit
+}
+if ($Console) {
+    if ([Console]::IsOutputRedirected -or [Console]::IsErrorRedirected) { [Console]::Error.WriteLine("Progress: 100%") }
+    else { Write-Progress -Id 1 -Activity "WindowsForum.com Diagnostic Tool" -Completed }
+    "Diagnostics complete in {0:mm\:ss}. Results have been saved to $zipFilePath." -f ((Get-Date) - $collectionStarted)
+    foreach ($note in $stopNote, $uploadNote, $splitNote, $passwordNote) { if ($note) { $note } }
+    $flagged = @($taskResults | Where-Object { $_.Severity -ne 'Info' })
+    if ($flagged) { "Tasks that need attention: $(($flagged | ForEach-Object { "$($_.Task) ($($_.Severity))" }) -join ', ')" }
+    if ($Interactive -and (Test-Path -Path "$filePath\manifest.json")) {
+        $outputs = @((Get-Content -Path "$filePath\manifest.json" -Raw | ConvertFrom-Json).Files | Group-Object Task | Sort-Object Name)
+        do {
+            ""
+            for ($i = 0; $i -lt $outputs.Count; $i++) { "{0,3}. {1} ({2} files)" -f ($i + 1), $outputs[$i].Name, $outputs[$i].Count }
+            $answer = Read-Host "Number of a task to page through its output, Enter to quit"
+            if ($answer -match '^\d+$' -and [int]$answer -ge 1 -and [int]$answer -le $outputs.Count) {
+                foreach ($file in $outputs[[int]$answer - 1].Group) {
+                    "== $($file.Path)"
+                    if ($file.Path -match '\.(txt|csv|json|log|xml|ndjson)$') { Get-Content -Path (Join-Path $filePath $file.Path) | Out-Host -Paging }
+                    else { "(binary, $($file.Size) bytes)" }
+                }
+            }
+        } while ($answer)
+    }
+    $form.Dispose()
+    exit
+}
+$script:collectionFinished = $true
+$label.Text = Get-WFText Complete $zipFilePath
 $progressBar.Value = 100
+$form.TopMost = $false
 $form.Refresh()
 Start-Sleep -Seconds 2
 #Start-Process "https://windowsforum.com"
 # Open the .zip file
-Invoke-Item -Path $zipFilePath
-$form.Close()
+if ($passwordNote -or $splitNote) { Start-Process explorer.exe -ArgumentList "/select,`"$zipFilePath`"" } else { Invoke-Item -Path $zipFilePath }
 # Show a popup window with the location of the zip file
-[System.Windows.Forms.MessageBox]::Show("Results have been saved to $zipFilePath. ", "Log Collection Complete", [System.Windows.Forms.MessageBoxButtons]::OK, [System.Windows.Forms.MessageBoxIcon]::Information, [System.Windows.Forms.MessageBoxDefaultButton]::Button1, [System.Windows.Forms.MessageBoxOptions]::ServiceNotification)
-}$allOutput = @()
-
-# Your existing code here...
-###NOT WORKING - COMBINE INTO HTML
-foreach ($task in $diagnosticTasks) {
-    $job = Start-Job -ScriptBlock $task.Task -ArgumentList $filePath, $zipFilePath
-    $jobs += $job
-    $result = Receive-Job -Job $job -Wait
-    $allOutput += New-Object PSObject -Property @{
-        'Task' = $task.Name
-        'Output' = $result
-    }
-    # Your existing code here...
+[System.Windows.Forms.MessageBox]::Show("$(Get-WFText SavedTo $zipFilePath) $stopNote $uploadNote $splitNote $passwordNote", (Get-WFText CompleteTitle), [System.Windows.Forms.MessageBoxButtons]::OK, [System.Windows.Forms.MessageBoxIcon]::Information, [System.Windows.Forms.MessageBoxDefaultButton]::Button1, [System.Windows.Forms.MessageBoxOptions]::ServiceNotification)
+# The window stays open so the output of each task can still be looked at
+$label.Text = Get-WFText CompleteBrowse $zipFilePath
+$failedTasks = @($taskResults | Where-Object { $_.State -ne 'Completed' })
+if ($failedTasks) {
+    $label.Text = Get-WFText CompleteFailed $failedTasks.Count ($failedTasks.Task -join ', ')
+    # The same selection as this run; tasks it skipped stay skipped and -Resume keeps the ones that completed
+    $retryParameters = @{}
+    $PSBoundParameters.GetEnumerator() | Where-Object { $_.Key -notin "Resume", "Exclude", "SelectTasks", "Interactive" } | ForEach-Object { $retryParameters[$_.Key] = $_.Value }
+    if ($skippedTasks) { $retryParameters["Exclude"] = @($skippedTasks.Task) }
+    $retryArguments = "$(ConvertTo-WFArgumentString (Get-WFRelaunchParameters $retryParameters)) -Resume"
+    $stopButton.Visible = $false
+    $pauseButton.Visible = $false
+    $retryButton.Visible = $true
+}
+while ($form.Visible) {
+    [System.Windows.Forms.Application]::DoEvents()
+    Start-Sleep -Milliseconds 100
+}
+$form.Dispose()
 }
-
-# Your existing code here...
-
-# Convert all output to HTML
-$html = $allOutput | ConvertTo-Html -Property Task, Output
-# Save HTML to file
-$html | Out-File "$filePath\WindowsForum-AllOutput.html"
-
-# Your existing code here...